@@ -0,0 +1,23 @@
+-- Add migration script here
+-- Change data capture for list_am_houses: one row per observed edit
+
+CREATE TABLE IF NOT EXISTS houses_data.list_am_house_versions (
+    id BIGSERIAL PRIMARY KEY,
+
+    house_id BIGINT NOT NULL
+        REFERENCES houses_data.list_am_houses(id)
+        ON DELETE CASCADE,
+
+    version INTEGER NOT NULL,
+    changed_fields TEXT[] NOT NULL DEFAULT '{}', -- empty for the first snapshot
+    diff JSONB NOT NULL DEFAULT '{}'::jsonb,     -- { field: { old, new } }
+    snapshot JSONB NOT NULL,                     -- tracked fields after the change
+
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    CONSTRAINT uq_list_am_house_versions_house_version
+        UNIQUE (house_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_house_versions_house_id
+    ON houses_data.list_am_house_versions (house_id);
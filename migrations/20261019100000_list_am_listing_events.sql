@@ -0,0 +1,54 @@
+-- Add migration script here
+-- Listing lifecycle: created -> deleted -> reactivated -> deleted ...
+
+ALTER TABLE houses_data.list_am_houses
+    ADD COLUMN IF NOT EXISTS relist_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS houses_data.list_am_listing_events (
+    id BIGSERIAL PRIMARY KEY,
+
+    house_id BIGINT NOT NULL
+        REFERENCES houses_data.list_am_houses(id)
+        ON DELETE CASCADE,
+
+    event_type TEXT NOT NULL, -- created | deleted | reactivated
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_listing_events_house_id
+    ON houses_data.list_am_listing_events (house_id, occurred_at);
+
+-- Backfill what we already know about existing rows
+INSERT INTO houses_data.list_am_listing_events (house_id, event_type, occurred_at)
+SELECT id, 'created', COALESCE(created_at, scraped_at)
+FROM houses_data.list_am_houses;
+
+INSERT INTO houses_data.list_am_listing_events (house_id, event_type, occurred_at)
+SELECT id, 'deleted', deleted_at
+FROM houses_data.list_am_houses
+WHERE deleted_at IS NOT NULL;
+
+/* ============================================================
+   list_am_listing_periods
+   One row per continuous on-market period, for time-on-market
+   analysis. ended_at is NULL while the listing is still active.
+   ============================================================ */
+
+CREATE OR REPLACE VIEW houses_data.list_am_listing_periods AS
+SELECT
+    s.house_id,
+    s.event_type AS started_by,
+    s.occurred_at AS started_at,
+    d.occurred_at AS ended_at,
+    COALESCE(d.occurred_at, now()) - s.occurred_at AS time_on_market
+FROM houses_data.list_am_listing_events s
+LEFT JOIN LATERAL (
+    SELECT e.occurred_at
+    FROM houses_data.list_am_listing_events e
+    WHERE e.house_id = s.house_id
+      AND e.event_type = 'deleted'
+      AND (e.occurred_at, e.id) > (s.occurred_at, s.id)
+    ORDER BY e.occurred_at, e.id
+    LIMIT 1
+) d ON true
+WHERE s.event_type IN ('created', 'reactivated');
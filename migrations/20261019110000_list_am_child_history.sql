@@ -0,0 +1,39 @@
+-- Add migration script here
+-- Reconcile child tables on re-scrape, keeping what was removed
+
+/* ============================================================
+   list_am_images
+   One row per house + url, so positions can be updated in place
+   ============================================================ */
+
+DELETE FROM houses_data.list_am_images a
+USING houses_data.list_am_images b
+WHERE a.house_id = b.house_id
+  AND a.url = b.url
+  AND a.id > b.id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS
+    idx_list_am_images_house_url_unique
+ON houses_data.list_am_images (house_id, url);
+
+
+/* ============================================================
+   list_am_child_history
+   Images, features and phones that disappeared from a listing
+   ============================================================ */
+
+CREATE TABLE IF NOT EXISTS houses_data.list_am_child_history (
+    id BIGSERIAL PRIMARY KEY,
+
+    house_id BIGINT NOT NULL
+        REFERENCES houses_data.list_am_houses(id)
+        ON DELETE CASCADE,
+
+    kind TEXT NOT NULL,  -- image | feature | phone
+    data JSONB NOT NULL, -- the removed row, without id / house_id
+
+    removed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_child_history_house_id
+    ON houses_data.list_am_child_history (house_id, kind);
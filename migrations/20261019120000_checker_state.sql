@@ -0,0 +1,13 @@
+-- Add migration script here
+-- Resumable cursor for the removal checker (keyset over list_am_houses.id)
+
+CREATE TABLE IF NOT EXISTS houses_data.checker_state (
+    job_name TEXT PRIMARY KEY,
+
+    last_id BIGINT NOT NULL DEFAULT 0, -- last house id fully processed
+
+    run_started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    run_finished_at TIMESTAMPTZ,       -- NULL while a run is in progress
+
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- A listing is only marked deleted after two removed verdicts
+-- separated in time; the first one is remembered here.
+
+ALTER TABLE houses_data.list_am_houses
+    ADD COLUMN IF NOT EXISTS removal_suspected_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS removal_reason TEXT;
@@ -0,0 +1,26 @@
+-- Add migration script here
+-- One row per removal-checker probe, for auditing verdicts and
+-- computing availability timelines
+
+CREATE TABLE IF NOT EXISTS houses_data.list_am_checks (
+    id BIGSERIAL PRIMARY KEY,
+
+    house_id BIGINT NOT NULL
+        REFERENCES houses_data.list_am_houses(id)
+        ON DELETE CASCADE,
+
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    method TEXT NOT NULL,   -- RANGE | GET (the request that decided)
+    http_status SMALLINT,   -- NULL on network errors
+    final_url TEXT,         -- after redirects
+    latency_ms INTEGER NOT NULL,
+
+    verdict TEXT NOT NULL,  -- active | removed | unknown
+    reason TEXT,
+    error_kind TEXT,        -- timeout | connect | redirect | body | request
+    action TEXT NOT NULL    -- none | suspected | confirmed | cleared
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_checks_house_id
+    ON houses_data.list_am_checks (house_id, checked_at);
@@ -0,0 +1,14 @@
+-- Add migration script here
+-- Priority-based recheck scheduling for the removal checker
+
+ALTER TABLE houses_data.list_am_houses
+    ADD COLUMN IF NOT EXISTS next_check_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_list_am_houses_next_check_at
+    ON houses_data.list_am_houses (next_check_at, id)
+    WHERE deleted_at IS NULL;
+
+-- The checker cursor is now (next_check_at, id)
+ALTER TABLE houses_data.checker_state
+    ADD COLUMN IF NOT EXISTS last_next_check_at TIMESTAMPTZ;
@@ -0,0 +1,32 @@
+-- Add migration script here
+-- Daemon bookkeeping: one row per scheduled job run, plus the daily
+-- aggregates computed by the `aggregates` job.
+
+CREATE TABLE IF NOT EXISTS houses_data.job_runs (
+    id BIGSERIAL PRIMARY KEY,
+
+    job_name TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at TIMESTAMPTZ,           -- NULL while running
+
+    status TEXT NOT NULL DEFAULT 'running', -- running | succeeded | failed
+    counters JSONB NOT NULL DEFAULT '{}'::jsonb,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_job_started
+    ON houses_data.job_runs (job_name, started_at DESC);
+
+ALTER TABLE houses_data.list_am_images
+    ADD COLUMN IF NOT EXISTS downloaded_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS houses_data.list_am_daily_stats (
+    day DATE PRIMARY KEY,
+
+    active_count INTEGER NOT NULL,     -- listings not deleted at end of day
+    created_count INTEGER NOT NULL,
+    reactivated_count INTEGER NOT NULL,
+    deleted_count INTEGER NOT NULL,
+
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
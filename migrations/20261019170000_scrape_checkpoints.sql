@@ -0,0 +1,22 @@
+-- Add migration script here
+-- Progress of each scrape run, so an interrupted run can be resumed
+-- with `scraper --resume`.
+
+CREATE TABLE IF NOT EXISTS houses_data.scrape_checkpoints (
+    id BIGSERIAL PRIMARY KEY,
+
+    category TEXT NOT NULL,            -- listing base URL
+    start_page INTEGER NOT NULL,
+    end_page INTEGER NOT NULL,
+
+    page INTEGER NOT NULL,             -- page in progress
+    last_external_id TEXT,             -- last item saved on that page
+
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at TIMESTAMPTZ            -- NULL while running or interrupted
+);
+
+CREATE INDEX IF NOT EXISTS idx_scrape_checkpoints_unfinished
+    ON houses_data.scrape_checkpoints (category, started_at DESC)
+    WHERE finished_at IS NULL;
@@ -0,0 +1,25 @@
+-- Add migration script here
+-- Work queue between listing discovery and detail fetching: `discover`
+-- enqueues item links, `worker` processes claim them with
+-- FOR UPDATE SKIP LOCKED.
+
+CREATE TABLE IF NOT EXISTS houses_data.detail_queue (
+    id BIGSERIAL PRIMARY KEY,
+
+    external_id TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+
+    status TEXT NOT NULL DEFAULT 'pending', -- pending | done | failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+
+    available_at TIMESTAMPTZ NOT NULL DEFAULT now(), -- retry backoff
+    locked_until TIMESTAMPTZ,          -- visibility timeout of a claim
+
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_detail_queue_pending
+    ON houses_data.detail_queue (available_at, id)
+    WHERE status = 'pending';
@@ -0,0 +1,34 @@
+-- Add migration script here
+-- Audit of every `scraper` run: what it was configured to do and what
+-- it did. A resumed run gets its own row, pointing at the checkpoint
+-- it continued.
+
+CREATE TABLE IF NOT EXISTS houses_data.scrape_runs (
+    id BIGSERIAL PRIMARY KEY,
+
+    checkpoint_id BIGINT
+        REFERENCES houses_data.scrape_checkpoints(id)
+        ON DELETE SET NULL,
+    resumed BOOLEAN NOT NULL DEFAULT false,
+    config JSONB NOT NULL,
+
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    finished_at TIMESTAMPTZ,
+    status TEXT NOT NULL DEFAULT 'running', -- running | succeeded | interrupted | failed
+
+    pages_visited INTEGER NOT NULL DEFAULT 0,
+    links_found INTEGER NOT NULL DEFAULT 0,
+    items_new INTEGER NOT NULL DEFAULT 0,
+    items_updated INTEGER NOT NULL DEFAULT 0,
+    items_unchanged INTEGER NOT NULL DEFAULT 0,
+    items_failed INTEGER NOT NULL DEFAULT 0,
+
+    requests BIGINT NOT NULL DEFAULT 0,
+    bytes_downloaded BIGINT NOT NULL DEFAULT 0,
+    duration_ms BIGINT,
+
+    errors JSONB NOT NULL DEFAULT '{}'::jsonb -- { "<stage>:<kind>": count }
+);
+
+CREATE INDEX IF NOT EXISTS idx_scrape_runs_started_at
+    ON houses_data.scrape_runs (started_at DESC);
@@ -0,0 +1,14 @@
+-- Add migration script here
+-- The listing base URL a listing was found under, as in
+-- scrape_checkpoints.category. Not on the item page itself, so it is
+-- carried from the listing page (and through detail_queue) to the save.
+-- NULL for listings saved before this column and by `item --save`.
+
+ALTER TABLE houses_data.list_am_houses
+    ADD COLUMN IF NOT EXISTS category TEXT;
+
+ALTER TABLE houses_data.detail_queue
+    ADD COLUMN IF NOT EXISTS category TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_list_am_houses_category
+    ON houses_data.list_am_houses (category);
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Owner of a detail_queue claim. Every update a worker makes to a
+-- claimed row checks the token, so a worker whose claim expired and
+-- was taken over cannot overwrite the new claimant's state.
+
+ALTER TABLE houses_data.detail_queue
+    ADD COLUMN IF NOT EXISTS claim_token TEXT;
@@ -0,0 +1,10 @@
+-- Add migration script here
+-- Category of the listings saved before it was recorded, from the queue
+-- entry they were fetched through where there is one
+
+UPDATE houses_data.list_am_houses h
+SET category = q.category
+FROM houses_data.detail_queue q
+WHERE h.category IS NULL
+  AND q.external_id = h.external_id
+  AND q.category IS NOT NULL;
@@ -0,0 +1,25 @@
+-- Add migration script here
+-- Category of the listings the direct scraper saved before it was
+-- recorded: the category of the scrape run whose time window holds the
+-- listing's last save. Listings inside the windows of runs of different
+-- categories, and those saved before runs were recorded, stay NULL.
+
+UPDATE houses_data.list_am_houses h
+SET category = m.category
+FROM (
+    SELECT h2.id, MIN(w.category) AS category
+    FROM houses_data.list_am_houses h2
+    JOIN (
+        SELECT
+            COALESCE(c.category, r.config->>'base_url') AS category,
+            r.started_at,
+            COALESCE(r.finished_at, c.updated_at, r.started_at) AS ended_at
+        FROM houses_data.scrape_runs r
+        LEFT JOIN houses_data.scrape_checkpoints c ON c.id = r.checkpoint_id
+    ) w ON h2.scraped_at BETWEEN w.started_at AND w.ended_at
+    WHERE h2.category IS NULL
+      AND w.category IS NOT NULL
+    GROUP BY h2.id
+    HAVING COUNT(DISTINCT w.category) = 1
+) m
+WHERE h.id = m.id;
@@ -0,0 +1,10 @@
+-- An interrupted page is resumed by skipping the items it already saved,
+-- not those ordered before the last one: listing pages are not sorted by
+-- external id. Checkpoints taken before this lose their partial page,
+-- which is crawled again in full.
+
+ALTER TABLE houses_data.scrape_checkpoints
+    ADD COLUMN IF NOT EXISTS saved_external_ids TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE houses_data.scrape_checkpoints
+    DROP COLUMN IF EXISTS last_external_id;
@@ -0,0 +1,160 @@
+-- SQLite schema for local runs: the tables the scrape and removal-check
+-- pipelines write, with the Postgres column names. Timestamps are
+-- RFC 3339 text in UTC, JSON is text.
+
+CREATE TABLE IF NOT EXISTS list_am_houses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    external_id TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL,
+
+    title TEXT,
+    price TEXT,
+    seller_name TEXT,
+    condition TEXT,
+    rooms INTEGER,
+    house_area_m2 REAL,
+    land_area_m2 REAL,
+    construction_type TEXT,
+    floors INTEGER,
+    bathrooms INTEGER,
+    garage TEXT,
+    renovation TEXT,
+    furniture TEXT,
+    description TEXT,
+    location TEXT,
+    amenities TEXT,
+    comfort TEXT,
+    ceiling_height TEXT,
+    prepayment TEXT,
+    utility_payments TEXT,
+    lease_type TEXT,
+    minimum_rental_period TEXT,
+    sewerage TEXT,
+    parking TEXT,
+    entrance TEXT,
+    location_from_street TEXT,
+    elevator TEXT,
+    floor_area TEXT,
+
+    created_at TEXT,
+    updated_at TEXT,
+    scraped_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+
+    deleted_at TEXT,
+    relist_count INTEGER NOT NULL DEFAULT 0,
+    removal_suspected_at TEXT,
+    removal_reason TEXT,
+    next_check_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    last_checked_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_houses_next_check_at
+    ON list_am_houses (next_check_at, id)
+    WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS list_am_phones (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    raw TEXT NOT NULL,
+    display TEXT NOT NULL,
+    source TEXT NOT NULL,
+    UNIQUE (house_id, source)
+);
+
+CREATE TABLE IF NOT EXISTS list_am_images (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    UNIQUE (house_id, url)
+);
+
+CREATE TABLE IF NOT EXISTS list_am_price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    date TEXT NOT NULL,
+    price TEXT NOT NULL,
+    diff TEXT,
+    UNIQUE (house_id, date, price, diff)
+);
+
+CREATE TABLE IF NOT EXISTS list_am_features (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    feature_type TEXT NOT NULL, -- appliances | service_lines | facilities
+    value TEXT NOT NULL,
+    UNIQUE (house_id, feature_type, value)
+);
+
+CREATE TABLE IF NOT EXISTS list_am_house_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    changed_fields TEXT NOT NULL DEFAULT '[]', -- JSON array
+    diff TEXT NOT NULL DEFAULT '{}',           -- { field: { old, new } }
+    snapshot TEXT NOT NULL,                    -- tracked fields after the change
+    recorded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE (house_id, version)
+);
+
+CREATE TABLE IF NOT EXISTS list_am_listing_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL, -- created | deleted | reactivated
+    occurred_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS list_am_checks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    checked_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    method TEXT NOT NULL,
+    http_status INTEGER,
+    final_url TEXT,
+    latency_ms INTEGER NOT NULL,
+    verdict TEXT NOT NULL,
+    reason TEXT,
+    error_kind TEXT,
+    action TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS checker_state (
+    job_name TEXT PRIMARY KEY,
+    last_id INTEGER NOT NULL DEFAULT 0,
+    last_next_check_at TEXT,
+    run_started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    run_finished_at TEXT,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS scrape_checkpoints (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    category TEXT NOT NULL,
+    start_page INTEGER NOT NULL,
+    end_page INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    last_external_id TEXT,
+    started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    finished_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS scrape_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    checkpoint_id INTEGER REFERENCES scrape_checkpoints(id) ON DELETE SET NULL,
+    resumed INTEGER NOT NULL DEFAULT 0,
+    config TEXT NOT NULL,
+    started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    finished_at TEXT,
+    status TEXT NOT NULL DEFAULT 'running',
+    pages_visited INTEGER NOT NULL DEFAULT 0,
+    links_found INTEGER NOT NULL DEFAULT 0,
+    items_new INTEGER NOT NULL DEFAULT 0,
+    items_updated INTEGER NOT NULL DEFAULT 0,
+    items_unchanged INTEGER NOT NULL DEFAULT 0,
+    items_failed INTEGER NOT NULL DEFAULT 0,
+    requests INTEGER NOT NULL DEFAULT 0,
+    bytes_downloaded INTEGER NOT NULL DEFAULT 0,
+    duration_ms INTEGER,
+    errors TEXT NOT NULL DEFAULT '{}'
+);
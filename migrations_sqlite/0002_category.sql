@@ -0,0 +1,3 @@
+-- The listing base URL a listing was found under.
+
+ALTER TABLE list_am_houses ADD COLUMN category TEXT;
@@ -0,0 +1,13 @@
+-- Images, features and phones that disappeared from a listing, as in
+-- the Postgres list_am_child_history.
+
+CREATE TABLE IF NOT EXISTS list_am_child_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL, -- image | feature | phone
+    data TEXT NOT NULL, -- the removed row as JSON, without id / house_id
+    removed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_list_am_child_history_house_id
+    ON list_am_child_history (house_id, kind);
@@ -0,0 +1,19 @@
+-- Price history entries with an unparseable date are kept with a NULL
+-- date, as in Postgres. SQLite cannot drop NOT NULL, so the table is
+-- rebuilt.
+
+CREATE TABLE list_am_price_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    house_id INTEGER NOT NULL REFERENCES list_am_houses(id) ON DELETE CASCADE,
+    date TEXT,
+    price TEXT NOT NULL,
+    diff TEXT,
+    UNIQUE (house_id, date, price, diff)
+);
+
+INSERT INTO list_am_price_history_new (id, house_id, date, price, diff)
+SELECT id, house_id, date, price, diff FROM list_am_price_history;
+
+DROP TABLE list_am_price_history;
+
+ALTER TABLE list_am_price_history_new RENAME TO list_am_price_history;
@@ -0,0 +1,6 @@
+-- Items of the interrupted page already saved, as a JSON array; see the
+-- Postgres scrape_checkpoints.saved_external_ids.
+
+ALTER TABLE scrape_checkpoints ADD COLUMN saved_external_ids TEXT NOT NULL DEFAULT '[]';
+
+ALTER TABLE scrape_checkpoints DROP COLUMN last_external_id;
@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+use crate::crawler::models::{ContactInfo, ContactPhone, HouseDetails, PriceHistory};
+use crate::storage::postgres::PgStorage;
+
+/// Same page size as a list.am category page.
+const PAGE_SIZE: usize = 60;
+
+/// Prefix of synthetic external ids, so bench rows never collide with
+/// real listings and can be removed afterwards.
+const BENCH_PREFIX: &str = "bench-";
+
+/// Compare the row-by-row and the set-based upsert paths on `count`
+/// synthetic houses: a first pass inserting them, then a second pass
+/// re-saving them with a changed price (updates + version rows).
+pub async fn run_storage_bench(storage: &PgStorage, count: usize) -> anyhow::Result<()> {
+    let first = synthetic_houses(count, 0);
+    let second = synthetic_houses(count, 1);
+
+    storage.delete_houses_by_prefix(BENCH_PREFIX).await?;
+
+    info!(count, "Benchmarking row-by-row path");
+    let row_insert = time_pages(&first, |page| storage.save_houses_batch(page)).await?;
+    let row_update = time_pages(&second, |page| storage.save_houses_batch(page)).await?;
+
+    storage.delete_houses_by_prefix(BENCH_PREFIX).await?;
+
+    info!(count, "Benchmarking bulk path");
+    let bulk_insert = time_pages(&first, |page| storage.save_houses_bulk(page)).await?;
+    let bulk_update = time_pages(&second, |page| storage.save_houses_bulk(page)).await?;
+
+    storage.delete_houses_by_prefix(BENCH_PREFIX).await?;
+
+    println!("{} houses, {} per page", count, PAGE_SIZE);
+    println!("{:<10} {:>12} {:>12}", "", "row-by-row", "bulk");
+    print_row("insert", row_insert, bulk_insert);
+    print_row("update", row_update, bulk_update);
+
+    Ok(())
+}
+
+async fn time_pages<'a, F, Fut, T>(houses: &'a [HouseDetails], mut save: F) -> anyhow::Result<Duration>
+where
+    F: FnMut(&'a [HouseDetails]) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let started = Instant::now();
+
+    for page in houses.chunks(PAGE_SIZE) {
+        save(page).await?;
+    }
+
+    Ok(started.elapsed())
+}
+
+fn print_row(label: &str, row: Duration, bulk: Duration) {
+    println!(
+        "{:<10} {:>10}ms {:>10}ms  ({:.1}x)",
+        label,
+        row.as_millis(),
+        bulk.as_millis(),
+        row.as_secs_f64() / bulk.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn synthetic_houses(count: usize, revision: u32) -> Vec<HouseDetails> {
+    (0..count)
+        .map(|i| {
+            let external_id = format!("{}{}", BENCH_PREFIX, i);
+
+            HouseDetails {
+                url: format!("https://www.list.am/en/item/{}", external_id),
+                external_id,
+                title: Some(format!("Synthetic house {}", i)),
+                price: Some(format!("${}", 100_000 + i as u32 * 10 + revision)),
+                contact: ContactInfo {
+                    seller_name: Some("Bench Seller".to_string()),
+                    phones: vec![
+                        ContactPhone {
+                            raw: format!("09{:07}", i),
+                            display: format!("(09) {:07}", i),
+                            source: "direct".to_string(),
+                        },
+                        ContactPhone {
+                            raw: format!("3749{:07}", i),
+                            display: format!("+374 9{:07}", i),
+                            source: "viber".to_string(),
+                        },
+                    ],
+                },
+                images: (0..8)
+                    .map(|n| format!("s.list.am/bench/{}/{}.webp", i, n))
+                    .collect(),
+                price_history: vec![PriceHistory {
+                    date: "2025-12-07T00:00:00+00:00".to_string(),
+                    price: format!("${}", 110_000 + i),
+                    diff: Some("-10,000".to_string()),
+                }],
+                condition: Some("Newly Built".to_string()),
+                rooms: Some((i % 6 + 1) as u8),
+                house_area_m2: Some(80.0 + (i % 200) as f32),
+                construction_type: Some("Stone".to_string()),
+                floors: Some(2),
+                bathrooms: Some(1),
+                garage: None,
+                renovation: Some("Designer Renovation".to_string()),
+                appliances: vec!["Refrigerator".to_string(), "Washing Machine".to_string()],
+                service_lines: vec!["Gas".to_string(), "Water".to_string()],
+                facilities: vec!["Balcony".to_string()],
+                furniture: Some("Available".to_string()),
+                land_area_m2: Some(300.0),
+                description: "Synthetic listing used by bench-storage".to_string(),
+                location: Some("Yerevan".to_string()),
+                created_at: Some("2026-01-02T13:23:00+00:00".to_string()),
+                updated_at: None,
+                amenities: None,
+                comfort: None,
+                ceiling_height: None,
+                prepayment: None,
+                utility_payments: None,
+                lease_type: None,
+                minimum_rental_period: None,
+                sewerage: None,
+                parking: None,
+                entrance: None,
+                location_from_street: None,
+                elevator: None,
+                floor_area: None,
+                category: None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,335 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use scraper::{Html, Selector};
+
+/// Why a listing is considered gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalKind {
+    Deleted,
+    Expired,
+    Sold,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListingStatus {
+    Active,
+    Removed { kind: RemovalKind, reason: String },
+    /// Nothing conclusive (network error, 5xx, unexpected markup).
+    Unknown { reason: String },
+}
+
+/// What the checker saw for one listing URL.
+#[derive(Debug)]
+pub struct ProbeResponse {
+    pub status: StatusCode,
+    pub final_url: String,
+    pub body: String,
+    /// Only the start of the page was received (206 to a ranged GET).
+    pub truncated: bool,
+}
+
+/// Notices list.am renders in place of the ad body, matched
+/// case-insensitively against the page text. Not yet checked against
+/// saved removed, expired and sold pages: those go in `fixtures/`, where
+/// the tests classify every one of them. A page with neither the ad
+/// title nor one of these stays `Unknown`, so a wrong string costs a
+/// verdict, not a listing.
+const REMOVAL_NOTICES: &[(&str, RemovalKind)] = &[
+    ("this ad has been deleted", RemovalKind::Deleted),
+    ("the ad has been deleted", RemovalKind::Deleted),
+    ("ad was deleted", RemovalKind::Deleted),
+    ("this ad has been removed", RemovalKind::Deleted),
+    ("this ad has expired", RemovalKind::Expired),
+    ("the ad has expired", RemovalKind::Expired),
+    ("this ad is no longer active", RemovalKind::Expired),
+    ("this item has been sold", RemovalKind::Sold),
+    ("marked as sold", RemovalKind::Sold),
+];
+
+impl fmt::Display for RemovalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            RemovalKind::Deleted => "deleted",
+            RemovalKind::Expired => "expired",
+            RemovalKind::Sold => "sold",
+        };
+        f.write_str(s)
+    }
+}
+
+impl ListingStatus {
+    /// Short label stored with each check.
+    pub fn verdict(&self) -> &'static str {
+        match self {
+            ListingStatus::Active => "active",
+            ListingStatus::Removed { .. } => "removed",
+            ListingStatus::Unknown { .. } => "unknown",
+        }
+    }
+
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            ListingStatus::Active => None,
+            ListingStatus::Removed { kind, reason } => Some(format!("{}: {}", kind, reason)),
+            ListingStatus::Unknown { reason } => Some(reason.clone()),
+        }
+    }
+
+    fn removed(kind: RemovalKind, reason: impl Into<String>) -> Self {
+        ListingStatus::Removed { kind, reason: reason.into() }
+    }
+
+    fn unknown(reason: impl Into<String>) -> Self {
+        ListingStatus::Unknown { reason: reason.into() }
+    }
+}
+
+/// Classify one probe of `https://www.list.am/.../item/<external_id>`.
+pub fn classify(external_id: &str, probe: &ProbeResponse) -> ListingStatus {
+    classify_traced(external_id, probe, &mut Vec::new())
+}
+
+/// Same as `classify`, appending one line per rule evaluated to `trace`.
+///
+/// Order matters: the HTTP status and redirect target are decisive on
+/// their own; the markup is only consulted for a 2xx on the item URL,
+/// because list.am serves its "ad deleted" page with a 200.
+pub fn classify_traced(
+    external_id: &str,
+    probe: &ProbeResponse,
+    trace: &mut Vec<String>,
+) -> ListingStatus {
+    let status = probe.status;
+    trace.push(format!("status {}, final url {}", status, probe.final_url));
+
+    if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+        trace.push("status means the ad is gone".to_string());
+        return ListingStatus::removed(RemovalKind::Deleted, format!("HTTP {}", status.as_u16()));
+    }
+
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        trace.push("server error or throttled, nothing can be concluded".to_string());
+        return ListingStatus::unknown(format!("HTTP {}", status.as_u16()));
+    }
+
+    // Deleted ads redirect to their category (or the home page)
+    if !is_item_url(&probe.final_url, external_id) {
+        trace.push(format!("final url is not /item/{}, treating as a redirect away", external_id));
+        return ListingStatus::removed(
+            RemovalKind::Deleted,
+            format!("redirected to {}", probe.final_url),
+        );
+    }
+    trace.push("final url is still the item page".to_string());
+
+    if !status.is_success() {
+        trace.push("unexpected non-2xx status".to_string());
+        return ListingStatus::unknown(format!("HTTP {}", status.as_u16()));
+    }
+
+    classify_body(&probe.body, trace)
+}
+
+/// Whether the start of the page already settles the probe. Only a
+/// truncated 2xx page showing neither the ad title nor a notice is
+/// worth fetching in full; a status or redirect verdict is final.
+pub fn partial_is_decisive(external_id: &str, probe: &ProbeResponse) -> bool {
+    !probe.truncated
+        || !probe.status.is_success()
+        || !matches!(classify(external_id, probe), ListingStatus::Unknown { .. })
+}
+
+fn classify_body(body: &str, trace: &mut Vec<String>) -> ListingStatus {
+    let doc = Html::parse_document(body);
+    trace.push(format!("checking markup of {} byte body", body.len()));
+
+    // A rendered ad always has its title; the notices are only looked
+    // for when it is missing, so an ad whose description happens to
+    // say "sold" is not flagged.
+    let title_sel = Selector::parse(r#"h1[itemprop="name"]"#).unwrap();
+    if doc.select(&title_sel).next().is_some() {
+        trace.push("ad title markup present".to_string());
+        return ListingStatus::Active;
+    }
+    trace.push("ad title markup missing, looking for removal notices".to_string());
+
+    let text = doc.root_element().text().collect::<String>().to_lowercase();
+
+    match REMOVAL_NOTICES
+        .iter()
+        .find(|(notice, _)| text.contains(notice))
+    {
+        Some((notice, kind)) => {
+            trace.push(format!("found notice \"{}\" ({})", notice, kind));
+            ListingStatus::removed(*kind, format!("page says \"{}\"", notice))
+        }
+        None => {
+            trace.push("no known notice found".to_string());
+            ListingStatus::unknown("200 without ad markup")
+        }
+    }
+}
+
+fn is_item_url(url: &str, external_id: &str) -> bool {
+    url.split('?')
+        .next()
+        .and_then(|path| path.split("/item/").nth(1))
+        .map(|id| id.trim_end_matches('/') == external_id)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    const ITEM_URL: &str = "https://www.list.am/en/item/123";
+
+    /// A GET of the item answered with `status`, `final_url` and `body`.
+    fn classify_get(status: u16, final_url: &str, body: &str) -> ListingStatus {
+        let probe = ProbeResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            final_url: final_url.to_string(),
+            body: body.to_string(),
+            truncated: false,
+        };
+        classify_traced("123", &probe, &mut Vec::new())
+    }
+
+    fn ad_page() -> &'static str {
+        r#"<html><body><h1 itemprop="name">House in Arabkir</h1><p>Marked as sold soon?</p></body></html>"#
+    }
+
+    #[test]
+    fn not_found_and_gone_are_deleted() {
+        for status in [404, 410] {
+            assert!(matches!(
+                classify_get(status, ITEM_URL, ""),
+                ListingStatus::Removed { kind: RemovalKind::Deleted, .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn redirect_away_from_the_item_is_deleted() {
+        let status = classify_get(200, "https://www.list.am/en/category/54", ad_page());
+        assert_eq!(
+            status,
+            ListingStatus::Removed {
+                kind: RemovalKind::Deleted,
+                reason: "redirected to https://www.list.am/en/category/54".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn item_url_with_query_or_slash_is_not_a_redirect() {
+        assert_eq!(classify_get(200, "https://www.list.am/en/item/123?lang=en", ad_page()), ListingStatus::Active);
+        assert_eq!(classify_get(200, "https://www.list.am/en/item/123/", ad_page()), ListingStatus::Active);
+        assert!(matches!(
+            classify_get(200, "https://www.list.am/en/item/1234", ad_page()),
+            ListingStatus::Removed { .. }
+        ));
+    }
+
+    #[test]
+    fn title_present_is_active_even_when_the_text_mentions_a_notice() {
+        assert_eq!(classify_get(200, ITEM_URL, ad_page()), ListingStatus::Active);
+    }
+
+    #[test]
+    fn notice_text_without_title_is_removed() {
+        let cases = [
+            ("<p>This ad has been DELETED by the user</p>", RemovalKind::Deleted),
+            ("<p>The ad has expired.</p>", RemovalKind::Expired),
+            ("<div>This item has been sold</div>", RemovalKind::Sold),
+        ];
+
+        for (markup, expected) in cases {
+            let body = format!("<html><body>{}</body></html>", markup);
+            match classify_get(200, ITEM_URL, &body) {
+                ListingStatus::Removed { kind, .. } => assert_eq!(kind, expected, "{}", markup),
+                other => panic!("{}: {:?}", markup, other),
+            }
+        }
+    }
+
+    #[test]
+    fn page_without_title_or_notice_is_unknown() {
+        assert!(matches!(
+            classify_get(200, ITEM_URL, "<html><body>Something else</body></html>"),
+            ListingStatus::Unknown { .. }
+        ));
+    }
+
+    #[test]
+    fn server_errors_and_throttling_are_unknown() {
+        for status in [500, 502, 503, 429] {
+            assert_eq!(
+                classify_get(status, "https://www.list.am/", ""),
+                ListingStatus::Unknown { reason: format!("HTTP {}", status) },
+            );
+        }
+    }
+
+    #[test]
+    fn partial_page_is_fetched_in_full_only_when_its_markup_is_unclear() {
+        let partial = |status: u16, final_url: &str, body: &str| ProbeResponse {
+            status: StatusCode::from_u16(status).unwrap(),
+            final_url: final_url.to_string(),
+            body: body.to_string(),
+            truncated: status == 206,
+        };
+
+        // Title or notice already in the first bytes
+        assert!(partial_is_decisive("123", &partial(206, ITEM_URL, ad_page())));
+        assert!(partial_is_decisive("123", &partial(206, ITEM_URL, "<p>This ad has expired</p>")));
+
+        // Cut off before either
+        assert!(!partial_is_decisive("123", &partial(206, ITEM_URL, "<html><head><title>")));
+
+        // Range ignored: the whole page came back
+        assert!(partial_is_decisive("123", &partial(200, ITEM_URL, "<html><head><title>")));
+
+        for status in [404, 410, 429, 500, 503] {
+            assert!(partial_is_decisive("123", &partial(status, ITEM_URL, "")), "{}", status);
+        }
+        assert!(partial_is_decisive("123", &partial(206, "https://www.list.am/en/category/54", "")));
+    }
+
+    /// Every saved page in `fixtures/` gets the verdict in its name.
+    #[test]
+    fn saved_pages_are_classified_by_their_name() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/checker/fixtures");
+
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "html") {
+                continue;
+            }
+
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            let (expected, external_id) = name.rsplit_once('-').unwrap();
+            let probe = ProbeResponse {
+                status: StatusCode::OK,
+                final_url: format!("https://www.list.am/en/item/{}", external_id),
+                body: std::fs::read_to_string(&path).unwrap(),
+                truncated: false,
+            };
+
+            let status = classify(external_id, &probe);
+            let got = match &status {
+                ListingStatus::Removed { kind, .. } => format!("removed-{}", kind),
+                other => other.verdict().to_string(),
+            };
+            assert_eq!(got, expected, "{}: {:?}", path.display(), status);
+        }
+    }
+
+    #[test]
+    fn other_non_success_on_the_item_is_unknown() {
+        assert!(matches!(classify_get(403, ITEM_URL, ""), ListingStatus::Unknown { .. }));
+    }
+}
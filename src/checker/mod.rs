@@ -1 +1,3 @@
+pub(crate) mod classifier;
+pub(crate) mod schedule;
 pub(crate) mod service;
\ No newline at end of file
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::checker::classifier::ListingStatus;
+
+/// Recheck soon after an inconclusive probe (network error, 5xx, 429).
+const AFTER_UNKNOWN: Duration = Duration::minutes(30);
+
+/// How long to wait before rechecking an active listing, by the age of
+/// its last renewal: fresh ads rarely disappear, stale ones often do.
+const ACTIVE_BY_AGE: &[(Duration, Duration)] = &[
+    (Duration::days(3), Duration::hours(72)),
+    (Duration::days(14), Duration::hours(24)),
+    (Duration::days(30), Duration::hours(12)),
+];
+const ACTIVE_STALE: Duration = Duration::hours(6);
+
+/// Each wait is scaled by a random factor in `1 ± JITTER`, so listings
+/// saved or probed together do not all come due together.
+const JITTER: f64 = 0.1;
+
+/// When the listing should be probed next, given this probe's verdict.
+///
+/// `listed_at` is the last renewal (or creation) time of the ad. A
+/// suspected removal is rechecked as soon as it can be confirmed.
+pub fn next_check_at(
+    status: &ListingStatus,
+    listed_at: Option<DateTime<Utc>>,
+    suspected_since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    confirm_after: Duration,
+) -> DateTime<Utc> {
+    let spread = rand::random_range(-1.0..=1.0);
+    scheduled_at(status, listed_at, suspected_since, now, confirm_after, spread)
+}
+
+/// When a listing saved at `now` is first probed: scheduled as if a
+/// probe had just found it active.
+pub fn first_check_at(listed_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+    next_check_at(&ListingStatus::Active, listed_at, None, now, Duration::zero())
+}
+
+/// [`next_check_at`] with the jitter given as `spread` in `-1.0..=1.0`.
+fn scheduled_at(
+    status: &ListingStatus,
+    listed_at: Option<DateTime<Utc>>,
+    suspected_since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    confirm_after: Duration,
+    spread: f64,
+) -> DateTime<Utc> {
+    let wait = match status {
+        ListingStatus::Unknown { .. } => AFTER_UNKNOWN,
+        ListingStatus::Removed { .. } => {
+            let since = suspected_since.unwrap_or(now);
+            return (since + confirm_after).max(now);
+        }
+        ListingStatus::Active => {
+            let age = listed_at.map(|t| now - t).unwrap_or(Duration::MAX);
+
+            ACTIVE_BY_AGE
+                .iter()
+                .find(|(max_age, _)| age < *max_age)
+                .map(|(_, wait)| *wait)
+                .unwrap_or(ACTIVE_STALE)
+        }
+    };
+
+    let jittered = wait.num_seconds() as f64 * (1.0 + JITTER * spread);
+    now + Duration::seconds(jittered as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::classifier::RemovalKind;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_800_000_000, 0).unwrap() + Duration::hours(hours)
+    }
+
+    fn removed() -> ListingStatus {
+        ListingStatus::Removed { kind: RemovalKind::Deleted, reason: "404".to_string() }
+    }
+
+    #[test]
+    fn active_waits_grow_shorter_with_age() {
+        let now = at(0);
+        let cases = [
+            (Some(Duration::hours(1)), 72),
+            (Some(Duration::days(3) - Duration::seconds(1)), 72),
+            (Some(Duration::days(3)), 24),
+            (Some(Duration::days(13)), 24),
+            (Some(Duration::days(14)), 12),
+            (Some(Duration::days(29)), 12),
+            (Some(Duration::days(30)), 6),
+            (Some(Duration::days(400)), 6),
+            (None, 6),
+        ];
+
+        for (age, wait_hours) in cases {
+            let listed_at = age.map(|age| now - age);
+            assert_eq!(
+                scheduled_at(&ListingStatus::Active, listed_at, None, now, Duration::zero(), 0.0),
+                at(wait_hours),
+                "age {:?}",
+                age
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_is_rechecked_soon() {
+        let status = ListingStatus::Unknown { reason: "timeout".to_string() };
+        let next = scheduled_at(&status, Some(at(-1)), None, at(0), Duration::hours(6), 0.0);
+
+        assert_eq!(next, at(0) + Duration::minutes(30));
+    }
+
+    #[test]
+    fn suspected_removal_is_rechecked_once_it_can_be_confirmed() {
+        let confirm_after = Duration::hours(6);
+        let cases = [
+            // First removed verdict: wait the whole confirmation delay
+            (None, at(6)),
+            // Suspected 2h ago: the rest of it
+            (Some(at(-2)), at(4)),
+            // Suspected long ago: right away
+            (Some(at(-48)), at(0)),
+        ];
+
+        for (suspected_since, expected) in cases {
+            for spread in [-1.0, 0.0, 1.0] {
+                let next = scheduled_at(&removed(), None, suspected_since, at(0), confirm_after, spread);
+                assert_eq!(next, expected, "suspected since {:?}", suspected_since);
+            }
+        }
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let now = at(0);
+        let fresh = Some(now - Duration::hours(1));
+
+        let earliest = scheduled_at(&ListingStatus::Active, fresh, None, now, Duration::zero(), -1.0);
+        let latest = scheduled_at(&ListingStatus::Active, fresh, None, now, Duration::zero(), 1.0);
+        assert_eq!(earliest, now + Duration::minutes(72 * 54));
+        assert_eq!(latest, now + Duration::minutes(72 * 66));
+
+        for _ in 0..1000 {
+            let next = next_check_at(&ListingStatus::Active, fresh, None, now, Duration::zero());
+            assert!((earliest..=latest).contains(&next), "{} out of bounds", next);
+        }
+    }
+
+    #[test]
+    fn first_check_is_scheduled_like_an_active_probe() {
+        let now = at(0);
+        let jitter = Duration::minutes(72);
+
+        for _ in 0..100 {
+            let next = first_check_at(Some(now - Duration::days(20)), now);
+            assert!((at(12) - jitter..=at(12) + jitter).contains(&next), "{} out of bounds", next);
+        }
+    }
+}
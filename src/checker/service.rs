@@ -1,90 +1,538 @@
-use crate::storage::postgres::Storage;
-use tracing::{info, warn};
-use tokio::time::{sleep, Duration};
+use std::path::Path;
+use std::sync::Arc;
 
-const BATCH_SIZE: i64 = 100;
+use crate::checker::classifier::{self, ListingStatus, ProbeResponse};
+use crate::checker::schedule;
+use crate::config::Config;
+use crate::{health, metrics};
+use crate::rate_limiter::{self, RateLimiter};
+use crate::shutdown::Shutdown;
+use crate::storage::{ActiveHouse, CheckRecord, Storage};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::header::{ACCEPT_ENCODING, RANGE};
+use reqwest::StatusCode;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, instrument, warn};
+use tokio::time::{Duration, Instant};
+
+/// Key of this job in `checker_state`.
+const JOB_NAME: &str = "removal_check";
+
+/// Bytes of the item page asked for by the first, ranged probe. A page
+/// cut off before its ad title or a notice is fetched again in full.
+const PARTIAL_BYTES: u64 = 64 * 1024;
+
+/// Result of probing one listing, with the HTTP evidence behind it.
+struct ProbeOutcome {
+    status: ListingStatus,
+    method: &'static str,
+    http_status: Option<u16>,
+    final_url: Option<String>,
+    error_kind: Option<&'static str>,
+    latency: Duration,
+    trace: Vec<String>,
+}
+
+/// What a verdict leads to for one listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    None,
+    /// First removed verdict: remember it, do not delete yet.
+    Suspect,
+    /// Removed again after the confirmation window: set `deleted_at`.
+    Confirm,
+    /// Active again after a suspicion: forget it.
+    Clear,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Action::None => "none",
+            Action::Suspect => "suspected",
+            Action::Confirm => "confirmed",
+            Action::Clear => "cleared",
+        }
+    }
+}
+
+/// Counters of one `run`.
+#[derive(Debug)]
+pub struct CheckStats {
+    pub checked: usize,
+    pub marked: usize,
+}
+
+impl CheckStats {
+    /// The counters kept in `job_runs`.
+    pub fn counters(&self) -> serde_json::Value {
+        serde_json::json!({ "checked": self.checked, "marked": self.marked })
+    }
+}
 
 pub struct RemovalCheckService {
-    storage: Storage,
+    storage: Arc<dyn Storage>,
     client: reqwest::Client,
+    limiter: RateLimiter,
+    concurrency: usize,
+    confirm_after: chrono::Duration,
+    budget: usize,
+    batch_size: i64,
 }
 
 impl RemovalCheckService {
-    pub fn new(storage: Storage) -> Self {
+    pub fn new(storage: Arc<dyn Storage>, cfg: &Config) -> Self {
         let client = reqwest::Client::builder()
+            .user_agent(&cfg.http.user_agent)
+            .timeout(Duration::from_secs(cfg.http.timeout_secs))
             .redirect(reqwest::redirect::Policy::limited(5))
             .build()
             .unwrap();
 
-        Self { storage, client }
+        Self {
+            storage,
+            client,
+            limiter: RateLimiter::new(Duration::from_millis(cfg.checker.delay_ms)),
+            concurrency: cfg.checker.concurrency,
+            confirm_after: chrono::Duration::minutes(cfg.checker.confirm_after_mins),
+            budget: cfg.checker.budget,
+            batch_size: cfg.checker.batch_size,
+        }
     }
 
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let mut offset = 0;
+    /// Probe the listings that are due, most overdue first, until none is
+    /// left or the per-run budget is spent. On shutdown the run stops
+    /// after the batch in progress and is resumed by the next one.
+    pub async fn run(&self, shutdown: &Shutdown) -> anyhow::Result<CheckStats> {
+        let run = self.storage.begin_checker_run(JOB_NAME).await?;
+        let mut cursor_at = run.cursor_at;
+        let mut cursor_id = run.cursor_id;
         let mut total_marked = 0usize;
 
-        loop {
+        if let Some(at) = cursor_at {
+            info!(cursor_at = %at, cursor_id, "Resuming interrupted removal check");
+        }
+
+        let due = self
+            .storage
+            .count_due_houses(run.started_at, cursor_at, cursor_id)
+            .await? as usize;
+        let total = due.min(self.budget);
+        let started = Instant::now();
+        let mut checked = 0usize;
+
+        info!(due, budget = self.budget, concurrency = self.concurrency, "Starting removal check");
+
+        while checked < total {
+            if shutdown.is_requested() {
+                warn!(checked, total_marked, "Removal check interrupted, the next run resumes it");
+                return Ok(CheckStats { checked, marked: total_marked });
+            }
+
+            let limit = self.batch_size.min((total - checked) as i64);
+
             let batch = self
                 .storage
-                .fetch_active_houses_batch(BATCH_SIZE, offset)
+                .fetch_due_houses_batch(limit, run.started_at, cursor_at, cursor_id)
                 .await?;
 
-            if batch.is_empty() {
+            let Some(last) = batch.last() else {
                 break;
-            }
+            };
+            let (batch_cursor_at, batch_cursor_id) = (last.next_check_at, last.id);
 
-            info!(
-                offset,
+            debug!(
+                cursor_id,
                 batch_size = batch.len(),
                 "Checking batch for removed pages"
             );
 
-            let mut removed_ids = Vec::new();
+            let outcomes = self.probe_batch(&batch).await;
+
+            let mut suspected_ids = Vec::new();
+            let mut suspected_reasons = Vec::new();
+            let mut confirmed_ids = Vec::new();
+            let mut cleared_ids = Vec::new();
+            let mut checks = Vec::with_capacity(outcomes.len());
+            let mut scheduled_ids = Vec::with_capacity(outcomes.len());
+            let mut scheduled_at = Vec::with_capacity(outcomes.len());
+            let now = Utc::now();
 
-            for (id, url) in &batch {
-                if self.page_is_removed(url).await {
-                    removed_ids.push(*id);
+            for (house, outcome) in outcomes {
+                scheduled_ids.push(house.id);
+                scheduled_at.push(self.next_check_at(house, &outcome.status, now));
+
+                let action = self.decide(house, &outcome.status, now);
+
+                debug!(
+                    external_id = %house.external_id,
+                    verdict = outcome.status.verdict(),
+                    reason = outcome.status.reason().unwrap_or_default(),
+                    action = action.as_str(),
+                    "Probed listing"
+                );
+
+                match action {
+                    Action::None => {}
+                    Action::Suspect => {
+                        suspected_ids.push(house.id);
+                        suspected_reasons.push(outcome.status.reason().unwrap_or_default());
+                    }
+                    Action::Confirm => confirmed_ids.push(house.id),
+                    Action::Clear => cleared_ids.push(house.id),
                 }
 
-                // polite delay per request
-                sleep(Duration::from_millis(200)).await;
+                checks.push(CheckRecord {
+                    house_id: house.id,
+                    method: outcome.method,
+                    http_status: outcome.http_status.map(|s| s as i16),
+                    final_url: outcome.final_url,
+                    latency_ms: outcome.latency.as_millis() as i32,
+                    verdict: outcome.status.verdict(),
+                    reason: outcome.status.reason(),
+                    error_kind: outcome.error_kind,
+                    action: action.as_str(),
+                });
             }
 
-            if !removed_ids.is_empty() {
+            self.storage.record_checks(&checks).await?;
+            self.storage
+                .schedule_next_checks(&scheduled_ids, &scheduled_at)
+                .await?;
+
+            self.storage
+                .flag_removal_suspected(&suspected_ids, &suspected_reasons)
+                .await?;
+            self.storage.clear_removal_suspected(&cleared_ids).await?;
+
+            if !confirmed_ids.is_empty() {
                 warn!(
-                    count = removed_ids.len(),
+                    count = confirmed_ids.len(),
                     "Marking houses as deleted"
                 );
 
                 self.storage
-                    .mark_houses_as_deleted(&removed_ids)
+                    .mark_houses_as_deleted(&confirmed_ids)
                     .await?;
 
-                total_marked += removed_ids.len();
+                total_marked += confirmed_ids.len();
+            }
+
+            cursor_at = Some(batch_cursor_at);
+            cursor_id = batch_cursor_id;
+            self.storage
+                .save_checker_cursor(JOB_NAME, batch_cursor_at, batch_cursor_id)
+                .await?;
+
+            checked += batch.len();
+            log_progress(checked, total, total_marked, started.elapsed());
+        }
+
+        self.storage.finish_checker_run(JOB_NAME).await?;
+
+        info!(checked, total_marked, elapsed_s = started.elapsed().as_secs(), "Removal check finished");
+        Ok(CheckStats { checked, marked: total_marked })
+    }
+
+    /// Probe the same listings `run` would, but write nothing: no cursor,
+    /// no check rows, no schedule, no suspicion, no deletion. Every
+    /// listing whose state would change is printed with its evidence and,
+    /// when `output` is given, written to it as one JSON object per line.
+    pub async fn dry_run(&self, output: Option<&Path>) -> anyhow::Result<()> {
+        let due_before = Utc::now();
+        let mut cursor_at = None;
+        let mut cursor_id = 0;
+
+        let due = self.storage.count_due_houses(due_before, None, 0).await? as usize;
+        let total = due.min(self.budget);
+        let started = Instant::now();
+        let mut checked = 0usize;
+        let mut would_mark = 0usize;
+
+        let mut file = match output {
+            Some(path) => Some(fs::File::create(path).await?),
+            None => None,
+        };
+
+        info!(due, budget = self.budget, "Starting removal check dry run");
+
+        while checked < total {
+            let limit = self.batch_size.min((total - checked) as i64);
+
+            let batch = self
+                .storage
+                .fetch_due_houses_batch(limit, due_before, cursor_at, cursor_id)
+                .await?;
+
+            let Some(last) = batch.last() else {
+                break;
+            };
+            (cursor_at, cursor_id) = (Some(last.next_check_at), last.id);
+
+            let now = Utc::now();
+
+            for (house, outcome) in self.probe_batch(&batch).await {
+                let action = self.decide(house, &outcome.status, now);
+                if action == Action::None {
+                    continue;
+                }
+
+                if action == Action::Confirm {
+                    would_mark += 1;
+                }
+
+                println!(
+                    "[would be {}] {} {} -> {} {} {} ({})",
+                    action.as_str(),
+                    house.external_id,
+                    house.url,
+                    outcome.method,
+                    outcome.http_status.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()),
+                    outcome.status.verdict(),
+                    outcome.status.reason().unwrap_or_default()
+                );
+
+                if let Some(f) = file.as_mut() {
+                    let line = serde_json::json!({
+                        "external_id": house.external_id,
+                        "url": house.url,
+                        "action": action.as_str(),
+                        "verdict": outcome.status.verdict(),
+                        "reason": outcome.status.reason(),
+                        "method": outcome.method,
+                        "http_status": outcome.http_status,
+                        "final_url": outcome.final_url,
+                        "error_kind": outcome.error_kind,
+                        "latency_ms": outcome.latency.as_millis() as u64,
+                        "removal_suspected_at": house.removal_suspected_at,
+                        "trace": outcome.trace,
+                    });
+                    f.write_all(format!("{}\n", line).as_bytes()).await?;
+                }
             }
 
-            offset += BATCH_SIZE;
+            checked += batch.len();
+            log_progress(checked, total, would_mark, started.elapsed());
+        }
+
+        if let Some(f) = file.as_mut() {
+            f.flush().await?;
+        }
+
+        info!(checked, would_mark, "Removal check dry run finished");
+        Ok(())
+    }
+
+    /// Probe a single listing and print how it was classified, what the
+    /// checker would do and when it would look again. Writes nothing.
+    pub async fn explain(&self, external_id: &str) -> anyhow::Result<()> {
+        let Some((house, deleted_at)) = self.storage.fetch_house_for_check(external_id).await? else {
+            println!("No listing with external id {}", external_id);
+            return Ok(());
+        };
+
+        println!("Listing {} (id {})", house.external_id, house.id);
+        println!("  url:             {}", house.url);
+        println!("  listed at:       {}", fmt_time(house.listed_at));
+        println!("  next check at:   {}", house.next_check_at.to_rfc3339());
+        println!("  suspected since: {}", fmt_time(house.removal_suspected_at));
+        println!("  deleted at:      {}", fmt_time(deleted_at));
+
+        let outcome = self.probe(&house).await;
+        let now = Utc::now();
+
+        println!("\nTrace");
+        for (i, step) in outcome.trace.iter().enumerate() {
+            println!("  {:>2}. {}", i + 1, step);
+        }
+
+        println!("\nVerdict");
+        println!("  {} ({})", outcome.status.verdict(), outcome.status.reason().unwrap_or_default());
+        println!(
+            "  decided by {} in {} ms",
+            outcome.method,
+            outcome.latency.as_millis()
+        );
+
+        if deleted_at.is_none() {
+            let action = self.decide(&house, &outcome.status, now);
+            println!("\nChecker would");
+            println!("  action:        {}", action.as_str());
+            println!(
+                "  check next at: {}",
+                self.next_check_at(&house, &outcome.status, now).to_rfc3339()
+            );
         }
 
-        info!(total_marked, "Removal check finished");
         Ok(())
     }
 
-    async fn page_is_removed(&self, url: &str) -> bool {
-        match self.client.get(url).send().await {
-            Ok(resp) if resp.status() == reqwest::StatusCode::NOT_FOUND => true,
+    async fn probe_batch<'a>(&self, batch: &'a [ActiveHouse]) -> Vec<(&'a ActiveHouse, ProbeOutcome)> {
+        // Boxed as `Send` explicitly: rustc cannot prove it through the
+        // closure's higher-ranked lifetimes when `run` is spawned.
+        let probes: BoxStream<'_, (&'a ActiveHouse, ProbeOutcome)> = stream::iter(batch)
+            .map(|house| async move { (house, self.probe(house).await) })
+            .buffer_unordered(self.concurrency)
+            .boxed();
 
-            Ok(resp) if resp.status().is_success() => false,
+        probes.collect().await
+    }
 
-            Ok(resp) => {
-                let body = resp.text().await.unwrap_or_default();
-                body.contains("not found")
-                    || body.contains("removed")
-                    || body.contains("deleted")
-                    || body.contains("no longer available")
+    /// Two removed verdicts at least `confirm_after` apart are needed
+    /// before a listing is marked deleted.
+    fn decide(&self, house: &ActiveHouse, status: &ListingStatus, now: DateTime<Utc>) -> Action {
+        match (status, house.removal_suspected_at) {
+            (ListingStatus::Active, Some(_)) => Action::Clear,
+            (ListingStatus::Removed { .. }, None) => Action::Suspect,
+            (ListingStatus::Removed { .. }, Some(since)) if now - since >= self.confirm_after => {
+                Action::Confirm
             }
+            _ => Action::None,
+        }
+    }
+
+    fn next_check_at(&self, house: &ActiveHouse, status: &ListingStatus, now: DateTime<Utc>) -> DateTime<Utc> {
+        schedule::next_check_at(
+            status,
+            house.listed_at,
+            house.removal_suspected_at,
+            now,
+            self.confirm_after,
+        )
+    }
 
-            Err(_) => false, // network errors ≠ removal
+    /// A ranged GET of the first `PARTIAL_BYTES` of the item page first;
+    /// the whole page is only fetched when that does not settle the
+    /// verdict (see `classifier::partial_is_decisive`).
+    #[instrument(name = "item", skip_all, fields(external_id = %house.external_id))]
+    async fn probe(&self, house: &ActiveHouse) -> ProbeOutcome {
+        let mut trace = Vec::new();
+
+        match self.fetch(&house.url, true).await {
+            Ok((probe, started)) if classifier::partial_is_decisive(&house.external_id, &probe) => {
+                trace.push(format!("{} byte range is decisive", probe.body.len()));
+                return outcome(&house.external_id, probe, "RANGE", started, trace);
+            }
+            Ok((probe, _)) => {
+                trace.push(format!("{} byte range is not decisive, falling back to GET", probe.body.len()));
+                metrics::record_retry("checker_get_fallback");
+            }
+            Err((e, _)) => {
+                debug!(url = %house.url, error = %e, "Range request failed, falling back to GET");
+                trace.push(format!("RANGE failed ({}), falling back to GET", error_kind(&e)));
+                metrics::record_retry("checker_get_fallback");
+            }
+        }
+
+        match self.fetch(&house.url, false).await {
+            Ok((probe, started)) => outcome(&house.external_id, probe, "GET", started, trace),
+            // network errors ≠ removal
+            Err((e, started)) => {
+                trace.push(format!("GET failed ({}): {}", error_kind(&e), e));
+
+                ProbeOutcome {
+                    status: ListingStatus::Unknown { reason: e.to_string() },
+                    method: "GET",
+                    http_status: e.status().map(|s| s.as_u16()),
+                    final_url: e.url().map(|u| u.to_string()),
+                    error_kind: Some(error_kind(&e)),
+                    latency: started.elapsed(),
+                    trace,
+                }
+            }
+        }
+    }
+
+    /// One rate-limited GET of `url` with its body, only the first
+    /// `PARTIAL_BYTES` when `ranged`. Also returns when it was sent.
+    async fn fetch(
+        &self,
+        url: &str,
+        ranged: bool,
+    ) -> Result<(ProbeResponse, Instant), (reqwest::Error, Instant)> {
+        self.limiter.acquire().await;
+        rate_limiter::acquire_host(url).await;
+        let started = Instant::now();
+
+        let mut req = self.client.get(url);
+        if ranged {
+            req = req
+                .header(RANGE, format!("bytes=0-{}", PARTIAL_BYTES - 1))
+                // A range of a compressed body could not be decoded
+                .header(ACCEPT_ENCODING, "identity");
         }
+
+        let res = req.send().await;
+        record_probe(&res, started);
+
+        let resp = res.map_err(|e| (e, started))?;
+        let status = resp.status();
+        let final_url = resp.url().to_string();
+        let body = resp.text().await.map_err(|e| (e, started))?;
+
+        let truncated = status == StatusCode::PARTIAL_CONTENT;
+        Ok((ProbeResponse { status, final_url, body, truncated }, started))
     }
 }
+
+/// The outcome of a probe that got a response.
+fn outcome(
+    external_id: &str,
+    probe: ProbeResponse,
+    method: &'static str,
+    started: Instant,
+    mut trace: Vec<String>,
+) -> ProbeOutcome {
+    let status = classifier::classify_traced(external_id, &probe, &mut trace);
+
+    ProbeOutcome {
+        status,
+        method,
+        http_status: Some(probe.status.as_u16()),
+        final_url: Some(probe.final_url),
+        error_kind: None,
+        latency: started.elapsed(),
+        trace,
+    }
+}
+
+fn record_probe(res: &reqwest::Result<reqwest::Response>, started: Instant) {
+    let status = res.as_ref().ok().map(|r| r.status().as_u16());
+    metrics::record_request("checker_probe", status, started.elapsed());
+    health::record_status(status);
+}
+
+fn error_kind(e: &reqwest::Error) -> &'static str {
+    if e.is_timeout() {
+        "timeout"
+    } else if e.is_connect() {
+        "connect"
+    } else if e.is_redirect() {
+        "redirect"
+    } else if e.is_body() || e.is_decode() {
+        "body"
+    } else {
+        "request"
+    }
+}
+
+fn fmt_time(t: Option<DateTime<Utc>>) -> String {
+    t.map(|t| t.to_rfc3339()).unwrap_or_else(|| "-".to_string())
+}
+
+fn log_progress(checked: usize, total: usize, marked: usize, elapsed: Duration) {
+    let rate = checked as f64 / elapsed.as_secs_f64().max(1.0);
+    let remaining = total.saturating_sub(checked);
+    let eta_s = if rate > 0.0 { (remaining as f64 / rate) as u64 } else { 0 };
+
+    info!(
+        checked,
+        total,
+        marked,
+        per_sec = format!("{:.1}", rate),
+        eta = format!("{}h{:02}m", eta_s / 3600, eta_s / 60 % 60),
+        "Removal check progress"
+    );
+}
@@ -0,0 +1,312 @@
+//! Command line. Settings come from the environment (and `.env`), then
+//! the `--config` file, then the flags of the command, each overriding
+//! the previous one.
+
+use std::path::PathBuf;
+
+use anyhow::bail;
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::Config;
+use crate::storage::postgres::ExportFilter;
+
+#[derive(Parser)]
+#[command(version, about = "Scrapes list.am real-estate listings into Postgres")]
+pub struct Cli {
+    /// TOML config file, overriding the environment
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Apply pending migrations instead of refusing to start
+    #[arg(long, global = true)]
+    pub auto_migrate: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Crawl the listing pages and save every item
+    #[command(alias = "scraper")]
+    Scrape {
+        #[command(flatten)]
+        scope: Scope,
+
+        /// Continue the last unfinished run of the category
+        #[arg(long)]
+        resume: bool,
+
+        /// Where the listings go: database, jsonl, csv, parquet
+        #[arg(long = "sink", value_name = "SINK", value_delimiter = ',')]
+        sinks: Vec<String>,
+    },
+
+    /// Check active listings for removal
+    #[command(alias = "checker")]
+    Check {
+        /// Probe and report, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write the dry-run report as JSON lines (JSONL) to FILE
+        #[arg(long, value_name = "FILE", requires = "dry_run")]
+        output: Option<PathBuf>,
+    },
+
+    /// Enqueue the item links of the listing pages for `worker`
+    Discover {
+        #[command(flatten)]
+        scope: Scope,
+    },
+
+    /// Fetch and save queued items
+    Worker {
+        /// Exit once the queue has nothing claimable
+        #[arg(long)]
+        drain: bool,
+    },
+
+    /// Run the scheduled jobs until stopped
+    Daemon,
+
+    /// Fetch one listing and print it as parsed, as JSON
+    Item {
+        /// External id or item URL
+        url_or_id: String,
+
+        /// Also save it, as a scrape would
+        #[arg(long)]
+        save: bool,
+    },
+
+    /// Probe one listing and explain the checker's verdict
+    Explain { external_id: String },
+
+    /// Print the change timeline of one listing
+    History { external_id: String },
+
+    /// List recent scrape runs
+    Runs {
+        #[arg(default_value_t = 20)]
+        limit: i64,
+    },
+
+    /// Print listing, image and queue counts and the daily aggregates
+    Stats,
+
+    /// Write listings with their child rows, one row per listing, to FILE
+    Export {
+        /// Output file; its extension picks the format unless --format is given
+        file: PathBuf,
+
+        /// csv, jsonl or parquet
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Columns to write, in order; all of them when omitted
+        #[arg(long, value_name = "COLUMN", value_delimiter = ',')]
+        columns: Vec<String>,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+
+    /// Manage the database schema
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Time the row and bulk save paths on synthetic listings
+    BenchStorage {
+        #[arg(default_value_t = 3000)]
+        count: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply the pending migrations
+    Run,
+    /// List the migrations and whether they are applied
+    Status,
+    /// Fail when the database is behind this binary
+    Check,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the merged settings as TOML, secrets masked
+    Show,
+}
+
+/// What to crawl, overriding the `[crawler]` page range and category.
+#[derive(Args)]
+pub struct Scope {
+    /// Listing pages, `N` or `FIRST-LAST`
+    #[arg(long, value_name = "RANGE", value_parser = parse_pages)]
+    pages: Option<(u32, u32)>,
+
+    /// Category id (`54`) or listing URL
+    #[arg(long)]
+    category: Option<String>,
+}
+
+/// Which listings `export` writes.
+#[derive(Args)]
+pub struct Filter {
+    /// Category id (`54`) or listing URL the listings were found under.
+    /// Listings saved before scrape runs were recorded can have none and
+    /// are left out
+    #[arg(long)]
+    category: Option<String>,
+
+    /// First day the listings were posted, `YYYY-MM-DD` (UTC)
+    #[arg(long, value_name = "DATE")]
+    from: Option<NaiveDate>,
+
+    /// Last day the listings were posted, `YYYY-MM-DD` (UTC)
+    #[arg(long, value_name = "DATE")]
+    to: Option<NaiveDate>,
+
+    /// Leave out listings removed from the site
+    #[arg(long)]
+    active: bool,
+
+    /// Only listings priced in this currency: usd, amd, eur or rub
+    #[arg(long, value_parser = parse_currency)]
+    currency: Option<&'static str>,
+
+    /// Lowest price, in --currency
+    #[arg(long, value_name = "AMOUNT", requires = "currency")]
+    min_price: Option<i64>,
+
+    /// Highest price, in --currency
+    #[arg(long, value_name = "AMOUNT", requires = "currency")]
+    max_price: Option<i64>,
+
+    /// District, matched anywhere in the location, ignoring case
+    #[arg(long)]
+    district: Option<String>,
+}
+
+impl Filter {
+    pub fn to_filter(&self) -> ExportFilter {
+        ExportFilter {
+            category: self.category.as_deref().map(category_url),
+            created_from: self.from,
+            created_to: self.to,
+            active_only: self.active,
+            currency: self.currency.map(str::to_string),
+            min_price: self.min_price,
+            max_price: self.max_price,
+            district: self.district.clone(),
+        }
+    }
+}
+
+impl Command {
+    /// Apply the flags that override settings.
+    pub fn apply(&self, cfg: &mut Config) {
+        if let Command::Scrape { sinks, .. } = self
+            && !sinks.is_empty()
+        {
+            cfg.output.sinks = sinks.clone();
+        }
+
+        let (Command::Scrape { scope, .. } | Command::Discover { scope }) = self else {
+            return;
+        };
+
+        if let Some((start, end)) = scope.pages {
+            cfg.crawler.start_page = start;
+            cfg.crawler.end_page = end;
+        }
+
+        if let Some(category) = &scope.category {
+            cfg.crawler.base_url = category_url(category);
+        }
+    }
+
+    /// Commands that need the database at this binary's schema.
+    pub fn needs_schema(&self) -> bool {
+        !matches!(
+            self,
+            Command::Migrate { .. } | Command::Config { .. } | Command::Item { save: false, .. }
+        )
+    }
+
+    /// Commands that open storage, so need `storage.database_url`.
+    pub fn needs_database(&self) -> bool {
+        !matches!(self, Command::Config { .. } | Command::Item { save: false, .. })
+    }
+
+    /// Commands that run on any `Storage` backend; the rest need
+    /// Postgres.
+    pub fn supports_sqlite(&self) -> bool {
+        matches!(
+            self,
+            Command::Scrape { .. }
+                | Command::Check { .. }
+                | Command::Explain { .. }
+                | Command::Item { .. }
+                | Command::Config { .. }
+        )
+    }
+
+    /// Modes worth watching through `/metrics` and the health checks.
+    pub fn is_long_running(&self) -> bool {
+        matches!(
+            self,
+            Command::Scrape { .. }
+                | Command::Check { dry_run: false, .. }
+                | Command::Discover { .. }
+                | Command::Worker { .. }
+                | Command::Daemon
+        )
+    }
+}
+
+fn parse_pages(s: &str) -> anyhow::Result<(u32, u32)> {
+    let (start, end) = match s.split_once('-') {
+        Some((a, b)) => (a.trim().parse()?, b.trim().parse()?),
+        None => {
+            let page = s.trim().parse()?;
+            (page, page)
+        }
+    };
+
+    if start == 0 || start > end {
+        bail!("expected N or FIRST-LAST with 1 <= FIRST <= LAST");
+    }
+
+    Ok((start, end))
+}
+
+/// `usd` -> `$`: the sign list.am prints prices with.
+fn parse_currency(s: &str) -> anyhow::Result<&'static str> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "usd" => "$",
+        "amd" => "֏",
+        "eur" => "€",
+        "rub" => "₽",
+        _ => bail!("expected usd, amd, eur or rub"),
+    })
+}
+
+/// `54` -> `https://www.list.am/en/category/54`; URLs pass through.
+fn category_url(category: &str) -> String {
+    if category.chars().all(|c| c.is_ascii_digit()) {
+        format!("https://www.list.am/en/category/{}", category)
+    } else {
+        category.trim_end_matches('/').to_string()
+    }
+}
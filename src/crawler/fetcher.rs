@@ -1,29 +1,77 @@
-use reqwest::Client;
-use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use reqwest::{Client, Response};
+use tokio::time::{Duration, Instant};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tracing::error;
 
-#[derive(Deserialize)]
-struct PhoneResponse {
-    phone: String,
-    name: Option<String>,
+use crate::config::HttpConfig;
+use crate::{health, metrics, rate_limiter};
+
+/// Requests sent and response bytes read on behalf of one run, for its
+/// report. Clones count into the same totals.
+#[derive(Clone, Default)]
+pub struct Traffic {
+    requests: Arc<AtomicU64>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Traffic {
+    /// `(requests, bytes)` so far.
+    pub fn totals(&self) -> (u64, u64) {
+        (self.requests.load(Ordering::Relaxed), self.bytes.load(Ordering::Relaxed))
+    }
+
+    fn count_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_bytes(&self, n: usize) {
+        self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+    }
 }
 
-pub fn build_client() -> Client {
+/// Short label of a fetch error, for per-kind counters.
+pub fn error_kind(e: &anyhow::Error) -> &'static str {
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(e) if e.is_timeout() => "timeout",
+        Some(e) if e.is_connect() => "connect",
+        Some(e) if e.is_status() => "status",
+        Some(e) if e.is_body() || e.is_decode() => "body",
+        Some(_) => "request",
+        None if e.downcast_ref::<std::io::Error>().is_some() => "io",
+        None => "other",
+    }
+}
+
+pub fn build_client(http: &HttpConfig) -> Client {
     Client::builder()
-        .user_agent("ListAm-Crawler/1.0 (approved)")
+        .user_agent(&http.user_agent)
+        .timeout(Duration::from_secs(http.timeout_secs))
         .build()
         .expect("failed to build http client")
 }
 
-pub async fn fetch_html(client: &Client, url: &str) -> anyhow::Result<String> {
-    let res = client.get(url).send().await?;
-    Ok(res.text().await?)
+/// `endpoint` labels the request in metrics (`listing`, `item`).
+pub async fn fetch_html(
+    client: &Client,
+    traffic: &Traffic,
+    url: &str,
+    endpoint: &str,
+) -> anyhow::Result<String> {
+    let res = send(client, traffic, url, endpoint).await?;
+    let text = res.text().await?;
+    traffic.count_bytes(text.len());
+
+    Ok(text)
 }
 
 pub async fn fetch_phone_popup_html(
     client: &Client,
+    traffic: &Traffic,
     item_id: &str,
 ) -> anyhow::Result<String> {
     let url = format!(
@@ -31,14 +79,16 @@ pub async fn fetch_phone_popup_html(
         item_id
     );
 
-    let res = client.get(url).send().await?;
+    let res = send(client, traffic, &url, "rtam_popup").await?;
     let text = res.text().await?;
+    traffic.count_bytes(text.len());
 
     Ok(text)
 }
 
 pub async fn download_images(
     client: &Client,
+    traffic: &Traffic,
     image_urls: &[String],
     item_id: &str,
 ) -> anyhow::Result<()> {
@@ -46,26 +96,23 @@ pub async fn download_images(
     fs::create_dir_all(&dir).await?;
 
     for (idx, url) in image_urls.iter().enumerate() {
-        let full_url = if url.starts_with("http://") || url.starts_with("https://") {
-            url.clone()
-        } else {
-            format!("https://{}", url)
-        };
+        let full_url = full_image_url(url);
 
-        let res = match client.get(&full_url).send().await {
+        let res = match send(client, traffic, &full_url, "image").await {
             Ok(r) => r,
             Err(e) => {
-                error!("Request failed for {}: {}", full_url, e);
+                error!(url = %full_url, error = %e, "Image request failed");
                 break;
             }
         };
 
         if !res.status().is_success() {
-            error!("Stopping image download at {}", full_url);
+            error!(url = %full_url, status = %res.status(), "Stopping image download");
             break;
         }
 
         let bytes = res.bytes().await?;
+        traffic.count_bytes(bytes.len());
 
         let filename = format!("{}/{}.webp", dir, idx + 1);
         let mut file = fs::File::create(&filename).await?;
@@ -73,4 +120,50 @@ pub async fn download_images(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Download one image to `path`. Returns the number of bytes written.
+pub async fn download_image(
+    client: &Client,
+    traffic: &Traffic,
+    url: &str,
+    path: &Path,
+) -> anyhow::Result<usize> {
+    let res = send(client, traffic, &full_image_url(url), "image").await?.error_for_status()?;
+    let bytes = res.bytes().await?;
+    traffic.count_bytes(bytes.len());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let mut file = fs::File::create(path).await?;
+    file.write_all(&bytes).await?;
+
+    Ok(bytes.len())
+}
+
+/// GET `url` once its host has a free slot, recording the status and
+/// latency under `endpoint`.
+async fn send(client: &Client, traffic: &Traffic, url: &str, endpoint: &str) -> reqwest::Result<Response> {
+    rate_limiter::acquire_host(url).await;
+    traffic.count_request();
+
+    let started = Instant::now();
+    let res = client.get(url).send().await;
+
+    let status = res.as_ref().ok().map(|r| r.status().as_u16());
+    metrics::record_request(endpoint, status, started.elapsed());
+    health::record_status(status);
+
+    res
+}
+
+/// Image URLs are stored without a scheme (`s.list.am/...`).
+fn full_image_url(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
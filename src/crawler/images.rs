@@ -0,0 +1,82 @@
+//! Image download job: fetches the files of saved image rows that have
+//! not been downloaded yet, into the same `images/<external_id>/` layout
+//! the crawler writes.
+
+use std::path::PathBuf;
+
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+use crate::config::{HttpConfig, ImagesConfig};
+use crate::crawler::fetcher;
+use crate::storage::postgres::PgStorage;
+
+#[derive(Debug, Default)]
+pub struct ImageStats {
+    pub downloaded: usize,
+    /// Already on disk (e.g. written by the crawler), only marked.
+    pub existing: usize,
+    pub failed: usize,
+    pub bytes: usize,
+}
+
+pub async fn download_pending(
+    storage: &PgStorage,
+    http: &HttpConfig,
+    cfg: &ImagesConfig,
+) -> anyhow::Result<ImageStats> {
+    let client = fetcher::build_client(http);
+    let traffic = fetcher::Traffic::default();
+    let mut stats = ImageStats::default();
+    let mut after_id = 0;
+
+    // Failed images keep `downloaded_at` NULL and are retried next run
+    while stats.downloaded + stats.existing + stats.failed < cfg.max_per_run {
+        let pending = storage.fetch_pending_images(after_id, cfg.batch_size).await?;
+        let Some(last) = pending.last() else {
+            break;
+        };
+        after_id = last.id;
+
+        let mut done = Vec::with_capacity(pending.len());
+
+        for image in &pending {
+            let path = PathBuf::from(format!(
+                "images/{}/{}.webp",
+                image.external_id,
+                image.position + 1
+            ));
+
+            if tokio::fs::try_exists(&path).await? {
+                stats.existing += 1;
+                done.push(image.id);
+                continue;
+            }
+
+            match fetcher::download_image(&client, &traffic, &image.url, &path).await {
+                Ok(bytes) => {
+                    stats.downloaded += 1;
+                    stats.bytes += bytes;
+                    done.push(image.id);
+                }
+                Err(e) => {
+                    warn!(external_id = %image.external_id, url = %image.url, error = %e, "Image download failed");
+                    stats.failed += 1;
+                }
+            }
+
+            sleep(Duration::from_millis(cfg.delay_ms)).await;
+        }
+
+        storage.mark_images_downloaded(&done).await?;
+    }
+
+    info!(
+        downloaded = stats.downloaded,
+        existing = stats.existing,
+        failed = stats.failed,
+        "Image download finished"
+    );
+
+    Ok(stats)
+}
@@ -1,97 +1,139 @@
-use std::collections::HashSet;
+use reqwest::Client;
 use tokio::time::{sleep, Duration};
-use tracing::{debug, info};
+use tracing::{info, info_span, instrument, Instrument};
+use crate::crawler::fetcher::Traffic;
 use crate::crawler::models::HouseDetails;
 use crate::config::Config;
+use crate::shutdown::Shutdown;
 
 mod fetcher;
+pub mod images;
 mod parser;
 pub mod models;
+pub mod queue;
 pub mod service;
 
+/// Details fetched for one listing page.
+pub struct CrawledPage {
+    pub houses: Vec<HouseDetails>,
+    /// `(external_id, error kind, error)` of the items that failed.
+    pub failures: Vec<(String, &'static str, anyhow::Error)>,
+}
+
+/// Fetch the details of every link, in order; a failed item is skipped.
+/// Stops early, after the item in progress, once shutdown is requested.
 pub async fn crawl_details(
+    cfg: &Config,
+    traffic: &Traffic,
     links: &[String],
-) -> anyhow::Result<Vec<HouseDetails>> {
-    let client = fetcher::build_client();
-    let mut results = Vec::new();
+    shutdown: &Shutdown,
+) -> CrawledPage {
+    let client = fetcher::build_client(&cfg.http);
+    let mut page = CrawledPage { houses: Vec::new(), failures: Vec::new() };
 
     for link in links {
-        let external_id = link
-            .split("/item/")
-            .nth(1)
-            .unwrap()
-            .split('?')
-            .next()
-            .unwrap()
-            .to_string();
-
-            info!("Fetching detail page for item {}", external_id);
-
-            // Fetch main item page
-            let html = fetcher::fetch_html(&client, link).await?;
-            let mut details = parser::scrape_house_details(&html, &external_id, link);
-            
-            // Fetch popup HTML
-            let popup_html =
-                fetcher::fetch_phone_popup_html(&client, &external_id).await?;
-            
-            // Parse contact info
-            let contact = parser::parse_contact_from_popup(&popup_html);
-            
-            // Assign contact info
-            details.contact = contact;
-
-            // parse images
-            let images = parser::parse_image_urls(&html);
-
-            details.images = images.clone();
-            fetcher::download_images(&client, &images, &external_id).await?;
-            
-            results.push(details);
-            
-            // polite delay
-            sleep(Duration::from_millis(300)).await;
-            
+        if shutdown.is_requested() {
+            break;
+        }
+
+        match crawl_item(&client, traffic, link).await {
+            Ok(mut details) => {
+                details.category = Some(cfg.crawler.base_url.clone());
+                page.houses.push(details);
+            }
+            Err(e) => {
+                let kind = fetcher::error_kind(&e);
+                page.failures.push((item_id(link).to_string(), kind, e));
+            }
+        }
 
         // polite delay
-        sleep(Duration::from_millis(300)).await;
+        sleep(Duration::from_millis(cfg.crawler.item_delay_ms)).await;
     }
 
-    Ok(results)
+    page
 }
 
-pub async fn crawl_first_pages(cfg: &Config) -> anyhow::Result<HashSet<String>> {
-    let client = fetcher::build_client();
-    let mut all_links: HashSet<String> = HashSet::new();
+/// Fetch one item page, its contact popup and its images.
+#[instrument(name = "item", skip_all, fields(external_id = item_id(link)))]
+async fn crawl_item(client: &Client, traffic: &Traffic, link: &str) -> anyhow::Result<HouseDetails> {
+    let details = fetch_item(client, traffic, link).await?;
+    fetcher::download_images(client, traffic, &details.images, &details.external_id).await?;
 
-    for page in cfg.start_page..=cfg.end_page {
-        let url = format!("{}/{}", cfg.base_url, page);
-        debug!("Fetching page {}", page);
+    Ok(details)
+}
 
-        let html = fetcher::fetch_html(&client, &url).await?;
-        let page_links = parser::extract_item_links(&html);
+/// Fetch and parse one listing by URL or external id, without
+/// downloading its images.
+pub async fn inspect_item(cfg: &Config, url_or_id: &str) -> anyhow::Result<HouseDetails> {
+    let link = if url_or_id.chars().all(|c| c.is_ascii_digit()) {
+        format!("https://www.list.am/en/item/{}", url_or_id)
+    } else if url_or_id.contains("/item/") {
+        url_or_id.to_string()
+    } else {
+        anyhow::bail!("expected an external id or a list.am item URL, got {:?}", url_or_id);
+    };
+
+    let client = fetcher::build_client(&cfg.http);
+    fetch_item(&client, &Traffic::default(), &link)
+        .instrument(info_span!("item", external_id = item_id(&link)))
+        .await
+}
 
-        all_links.extend(page_links);
+/// Item page and contact popup, parsed; the image URLs are listed but
+/// not downloaded.
+async fn fetch_item(client: &Client, traffic: &Traffic, link: &str) -> anyhow::Result<HouseDetails> {
+    let external_id = item_id(link).to_string();
 
-        sleep(Duration::from_millis(cfg.delay_ms)).await;
-    }
+    info!("Fetching detail page");
+
+    // Fetch main item page
+    let html = fetcher::fetch_html(client, traffic, link, "item").await?;
+    let mut details = parser::scrape_house_details(&html, &external_id, link);
 
-    Ok(all_links)
+    // Fetch popup HTML
+    let popup_html =
+        fetcher::fetch_phone_popup_html(client, traffic, &external_id).await?;
+
+    // Parse contact info
+    let contact = parser::parse_contact_from_popup(&popup_html);
+
+    // Assign contact info
+    details.contact = contact;
+
+    // parse images
+    details.images = parser::parse_image_urls(&html);
+
+    Ok(details)
 }
 
 pub async fn crawl_page_links(
     cfg: &Config,
+    traffic: &Traffic,
     page: u32,
 ) -> anyhow::Result<Vec<String>> {
-    let client = fetcher::build_client();
-    let url = format!("{}/{}", cfg.base_url, page);
+    let client = fetcher::build_client(&cfg.http);
+    let url = format!("{}/{}", cfg.crawler.base_url, page);
 
     tracing::info!(page, "Fetching listing page");
 
-    let html = fetcher::fetch_html(&client, &url).await?;
-    let links = parser::extract_item_links(&html)
+    let html = fetcher::fetch_html(&client, traffic, &url, "listing").await?;
+    let mut links: Vec<String> = parser::extract_item_links(&html)
         .into_iter()
         .collect();
 
+    // Stable order, so a resumed run can skip what was already saved
+    links.sort();
+
     Ok(links)
 }
+
+/// `https://www.list.am/en/item/<id>?...` -> `<id>`
+pub fn item_id(link: &str) -> &str {
+    link.split("/item/")
+        .nth(1)
+        .unwrap()
+        .split('?')
+        .next()
+        .unwrap()
+}
@@ -1,43 +1,26 @@
-#[derive(Debug, Clone)]
-pub struct ItemLink {
-    pub url: String,
-}
-
-#[derive(Debug)]
-pub struct PhoneContact {
-    pub raw: String,
-    pub display: String,
-    pub source: PhoneSource,
-}
-
-#[derive(Debug)]
-pub enum PhoneSource {
-    Direct,
-    Viber,
-    WhatsApp,
-}
+use serde::Serialize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ContactPhone {
     pub raw: String,
     pub display: String,
     pub source: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ContactInfo {
     pub seller_name: Option<String>,
     pub phones: Vec<ContactPhone>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct PriceHistory {
     pub date: String,
     pub price: String,
     pub diff: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize)]
 pub struct HouseDetails {
     pub external_id: String,
     pub url: String,  
@@ -76,4 +59,6 @@ pub struct HouseDetails {
     pub location_from_street: Option<String>,
     pub elevator: Option<String>,
     pub floor_area: Option<String>,
+    /// Listing base URL the item was found under; not on the item page.
+    pub category: Option<String>,
 }
@@ -1,25 +1,20 @@
 use scraper::{Html, Selector};
-use tracing::debug;
 use std::collections::HashSet;
 use crate::crawler::models::{HouseDetails, PriceHistory, ContactPhone};
 use regex::Regex;
 use crate::crawler::models::ContactInfo;
 use chrono::{DateTime, NaiveDateTime, Utc, NaiveDate};
-use crate::config::Config;
-use crate::crawler::fetcher;
-use tokio::time::{sleep, Duration};
-use crate::crawler::parser;
+use crate::metrics;
 
 fn normalize_price_date(raw: &str) -> Option<String> {
     // Example: "December 07, 2025"
     NaiveDate::parse_from_str(raw, "%B %d, %Y")
         .ok()
-        .map(|d| {
+        .and_then(|d| {
             let dt = d.and_hms_opt(0, 0, 0)?;
             let utc: DateTime<Utc> = DateTime::from_naive_utc_and_offset(dt, Utc);
             Some(utc.to_rfc3339())
         })
-        .flatten()
 }
 
 pub fn parse_price_history_iso(html: &str) -> Vec<PriceHistory> {
@@ -111,36 +106,17 @@ pub fn extract_item_links(html: &str) -> HashSet<String> {
     let mut links = HashSet::new();
 
     for el in document.select(&selector) {
-        if let Some(href) = el.value().attr("href") {
-            if href.starts_with("/en/item/") {
-                let clean = href.split('?').next().unwrap();
-                links.insert(format!("https://www.list.am{}", clean));
-            }
+        if let Some(href) = el.value().attr("href")
+            && href.starts_with("/en/item/")
+        {
+            let clean = href.split('?').next().unwrap();
+            links.insert(format!("https://www.list.am{}", clean));
         }
     }
 
     links
 }
 
-pub async fn crawl_first_pages(cfg: &Config) -> anyhow::Result<HashSet<String>> {
-    let client = fetcher::build_client();
-    let mut all_links: HashSet<String> = HashSet::new();
-
-    for page in cfg.start_page..=cfg.end_page {
-        let url = format!("{}/{}", cfg.base_url, page);
-        debug!("Fetching page {}", page);
-
-        let html = fetcher::fetch_html(&client, &url).await?;
-        let page_links = parser::extract_item_links(&html);
-
-        all_links.extend(page_links);
-
-        sleep(Duration::from_millis(cfg.delay_ms)).await;
-    }
-
-    Ok(all_links)
-}
-
 pub fn parse_image_urls(html: &str) -> Vec<String> {
     // Match: img:["url1","url2",...]
     let re = Regex::new(r#"img\s*:\s*\[(?P<list>[^\]]+)\]"#).unwrap();
@@ -202,11 +178,25 @@ pub fn scrape_house_details(
     };
 
     // Simple parsers
-    let parse_u8 = |v: Option<String>| v.and_then(|x| x.parse::<u8>().ok());
-    let parse_m2 = |v: Option<String>| {
+    // A label with a value that does not parse counts as a parse
+    // failure of `field`; a missing label does not.
+    let parse_u8 = |field: &str, v: Option<String>| {
+        v.and_then(|x| {
+            let parsed = x.parse::<u8>().ok();
+            if parsed.is_none() {
+                metrics::record_parse_failure(field);
+            }
+            parsed
+        })
+    };
+    let parse_m2 = |field: &str, v: Option<String>| {
         v.and_then(|x| {
             let cleaned = x.replace("sq.m.", "").replace("ք.մ", "").trim().to_string();
-            cleaned.parse::<f32>().ok()
+            let parsed = cleaned.parse::<f32>().ok();
+            if parsed.is_none() {
+                metrics::record_parse_failure(field);
+            }
+            parsed
         })
     };
     let split_csv = |v: Option<String>| -> Vec<String> {
@@ -230,11 +220,6 @@ pub fn scrape_house_details(
     // -------- Location --------
     let location = next_after("Location");
 
-    // -------- Price History --------
-    let price_tokens = section_between("Price History", "Description");
-
-    let re = Regex::new(r"^(?P<date>[A-Za-z]+\s+\d{2},\s+\d{4})(?P<rest>.*)$").unwrap();
-
         // -------- TITLE --------
     let title = Selector::parse(r#"h1[itemprop="name"]"#)
     .ok()
@@ -259,6 +244,17 @@ pub fn scrape_house_details(
     // -------- CREATED AT & UPDATED AT (HTML) --------
     let (created_at, updated_at) = parse_created_updated_iso(html);
 
+    // Every rendered ad has these
+    for (field, missing) in [
+        ("title", title.is_none()),
+        ("price", price.is_none()),
+        ("created_at", created_at.is_none()),
+    ] {
+        if missing {
+            metrics::record_parse_failure(field);
+        }
+    }
+
     // -------- Build final struct (label -> next token) --------
     HouseDetails {
         external_id: external_id.to_string(),
@@ -272,18 +268,18 @@ pub fn scrape_house_details(
         price_history,
         images: vec![],
         condition: next_after("Condition"),
-        rooms: parse_u8(next_after("Number of Rooms")),
-        house_area_m2: parse_m2(next_after("House Area")),
+        rooms: parse_u8("rooms", next_after("Number of Rooms")),
+        house_area_m2: parse_m2("house_area_m2", next_after("House Area")),
         construction_type: next_after("Construction Type"),
-        floors: parse_u8(next_after("Floors in the Building")),
-        bathrooms: parse_u8(next_after("Number of Bathrooms")),
+        floors: parse_u8("floors", next_after("Floors in the Building")),
+        bathrooms: parse_u8("bathrooms", next_after("Number of Bathrooms")),
         garage: next_after("Garage"),
         renovation: next_after("Renovation"),
         appliances: split_csv(next_after("Appliances")),
         service_lines: split_csv(next_after("Service Lines")),
         facilities: split_csv(next_after("Facilities")),
         furniture: next_after("Furniture"),
-        land_area_m2: parse_m2(next_after("Land Area")),
+        land_area_m2: parse_m2("land_area_m2", next_after("Land Area")),
         amenities: next_after("Amenities"),
         comfort: next_after("Comfort"),
         ceiling_height: next_after("Ceiling Height"),
@@ -297,6 +293,7 @@ pub fn scrape_house_details(
         location_from_street: next_after("Location from the Street"),
         elevator: next_after("Elevator"),
         floor_area: next_after("Floor Area"),
+        category: None,
         description,
         location,
         created_at,
@@ -352,18 +349,18 @@ pub fn parse_contact_from_popup(html: &str) -> ContactInfo {
     // --------------------
     if let Ok(sel) = Selector::parse(r#"a[href^="viber://chat"]"#) {
         for el in doc.select(&sel) {
-            if let Some(href) = el.value().attr("href") {
-                if let Some(num) = href.split("number=").nth(1) {
-                    let raw = num.trim_start_matches('+').to_string();
-                    let display = el.text().collect::<String>().trim().to_string();
-
-                    if seen.insert(raw.clone()) {
-                        phones.push(ContactPhone {
-                            raw,
-                            display,
-                            source: "viber".to_string(),
-                        });
-                    }
+            if let Some(href) = el.value().attr("href")
+                && let Some(num) = href.split("number=").nth(1)
+            {
+                let raw = num.trim_start_matches('+').to_string();
+                let display = el.text().collect::<String>().trim().to_string();
+
+                if seen.insert(raw.clone()) {
+                    phones.push(ContactPhone {
+                        raw,
+                        display,
+                        source: "viber".to_string(),
+                    });
                 }
             }
         }
@@ -399,23 +396,3 @@ pub fn parse_contact_from_popup(html: &str) -> ContactInfo {
         phones,
     }
 }
-
-fn split_list(value: Option<&String>) -> Vec<String> {
-    value
-        .map(|v| {
-            v.split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect()
-        })
-        .unwrap_or_default()
-}
-
-fn extract_width_px(style: &str) -> Option<u64> {
-    style
-        .split(';')
-        .find(|s| s.contains("width"))
-        .and_then(|w| w.split(':').nth(1))
-        .and_then(|v| v.trim().trim_end_matches("px").parse().ok())
-}
-
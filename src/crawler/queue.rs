@@ -0,0 +1,206 @@
+//! Split pipeline over `detail_queue`: `discover` walks the listing
+//! pages and enqueues item links, `worker` processes claim the links,
+//! fetch the details and save them. Failed items are retried with
+//! exponential backoff until `queue_max_attempts`; an item whose claims
+//! keep expiring (its worker died) is given up on after as many claims.
+//! Claims are renewed while a batch is processed.
+
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::crawler::{self, fetcher};
+use crate::metrics;
+use crate::shutdown::Shutdown;
+use crate::storage::postgres::{PgStorage, QueuedItem};
+
+/// Pause between polls of an empty queue.
+const IDLE_POLL: Duration = Duration::from_secs(10);
+
+/// Backoff cap between two attempts of one item.
+const MAX_BACKOFF_MINS: i64 = 360;
+
+#[derive(Debug, Default)]
+pub struct WorkerStats {
+    pub saved: usize,
+    pub retried: usize,
+    pub failed: usize,
+}
+
+/// Enqueue the item links of the configured page range. Returns the
+/// number of links queued (new or re-queued).
+pub async fn discover(cfg: &Config, storage: &PgStorage, shutdown: &Shutdown) -> anyhow::Result<u64> {
+    let traffic = fetcher::Traffic::default();
+    let mut total_queued = 0;
+
+    for page in cfg.crawler.start_page..=cfg.crawler.end_page {
+        if shutdown.is_requested() {
+            warn!(page, total_queued, "Discovery interrupted");
+            break;
+        }
+
+        let links = match crawler::crawl_page_links(cfg, &traffic, page).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(page, error = %e, "Failed to crawl page links");
+                continue;
+            }
+        };
+
+        let external_ids: Vec<String> = links
+            .iter()
+            .map(|link| crawler::item_id(link).to_string())
+            .collect();
+
+        let queued = storage.enqueue_details(&external_ids, &links, &cfg.crawler.base_url).await?;
+        total_queued += queued;
+
+        info!(page, found = links.len(), queued, total_queued, "Enqueued item links");
+
+        tokio::select! {
+            _ = sleep(Duration::from_millis(cfg.crawler.page_delay_ms)) => {}
+            _ = shutdown.requested() => {}
+        }
+    }
+
+    Ok(total_queued)
+}
+
+/// Claim and process queue items until shutdown, or, with `drain`,
+/// until nothing is claimable.
+pub async fn work(
+    cfg: &Config,
+    storage: &PgStorage,
+    shutdown: &Shutdown,
+    drain: bool,
+) -> anyhow::Result<WorkerStats> {
+    let client = fetcher::build_client(&cfg.http);
+    let traffic = fetcher::Traffic::default();
+    let visibility = chrono::Duration::seconds(cfg.crawler.queue_visibility_secs as i64);
+    let mut stats = WorkerStats::default();
+
+    while !shutdown.is_requested() {
+        let claim = storage
+            .claim_details(cfg.crawler.worker_batch, visibility, cfg.crawler.queue_max_attempts)
+            .await?;
+
+        if claim.abandoned > 0 {
+            warn!(count = claim.abandoned, "Gave up on items whose claims never finished");
+            stats.failed += claim.abandoned as usize;
+        }
+
+        let items = &claim.items;
+        let token = claim.token.as_str();
+
+        if items.is_empty() {
+            if drain {
+                break;
+            }
+
+            tokio::select! {
+                _ = sleep(IDLE_POLL) => {}
+                _ = shutdown.requested() => {}
+            }
+            continue;
+        }
+
+        let mut houses = Vec::with_capacity(items.len());
+        let mut fetched = Vec::with_capacity(items.len());
+        let mut unprocessed = Vec::new();
+
+        for item in items {
+            if shutdown.is_requested() {
+                unprocessed.push(item.id);
+                continue;
+            }
+
+            // A batch may take longer than one visibility timeout
+            storage.extend_claim(token, visibility).await?;
+
+            match crawler::crawl_item(&client, &traffic, &item.url).await {
+                Ok(mut details) => {
+                    details.category = item.category.clone();
+                    houses.push(details);
+                    fetched.push(item.id);
+                }
+                Err(e) => record_failure(cfg, storage, token, item, &format!("{:#}", e), &mut stats).await?,
+            }
+
+            // polite delay
+            sleep(Duration::from_millis(cfg.crawler.item_delay_ms)).await;
+        }
+
+        storage.release_details(&unprocessed, token).await?;
+
+        match storage.save_houses_bulk(&houses).await {
+            Ok(saved) => {
+                let completed = storage.complete_details(&fetched, token).await?;
+                if completed < fetched.len() as u64 {
+                    warn!(
+                        lost = fetched.len() as u64 - completed,
+                        "Claim expired before completion, items were taken by another worker"
+                    );
+                }
+
+                stats.saved += saved.saved();
+                info!(
+                    new = saved.new,
+                    updated = saved.updated,
+                    unchanged = saved.unchanged,
+                    total_saved = stats.saved,
+                    "Saved queued items"
+                );
+            }
+            Err(e) => {
+                let error = format!("save failed: {:#}", e);
+                for item in items.iter().filter(|i| fetched.contains(&i.id)) {
+                    record_failure(cfg, storage, token, item, &error, &mut stats).await?;
+                }
+            }
+        }
+    }
+
+    info!(saved = stats.saved, retried = stats.retried, failed = stats.failed, "Worker stopped");
+    Ok(stats)
+}
+
+async fn record_failure(
+    cfg: &Config,
+    storage: &PgStorage,
+    token: &str,
+    item: &QueuedItem,
+    error: &str,
+    stats: &mut WorkerStats,
+) -> anyhow::Result<()> {
+    if item.attempts >= cfg.crawler.queue_max_attempts {
+        if storage.fail_detail(item.id, token, error, None).await? {
+            warn!(external_id = %item.external_id, attempts = item.attempts, error, "Giving up on item");
+            stats.failed += 1;
+        } else {
+            warn!(external_id = %item.external_id, "Claim expired, failure not recorded");
+        }
+        return Ok(());
+    }
+
+    // 1, 2, 4, 8 ... minutes
+    let backoff = (1i64 << (item.attempts - 1).clamp(0, 16)).min(MAX_BACKOFF_MINS);
+    let retry_at = Utc::now() + chrono::Duration::minutes(backoff);
+
+    if !storage.fail_detail(item.id, token, error, Some(retry_at)).await? {
+        warn!(external_id = %item.external_id, "Claim expired, failure not recorded");
+        return Ok(());
+    }
+
+    warn!(
+        external_id = %item.external_id,
+        attempts = item.attempts,
+        retry_in_mins = backoff,
+        error,
+        "Item failed, will retry"
+    );
+    stats.retried += 1;
+    metrics::record_retry("detail_fetch");
+
+    Ok(())
+}
@@ -1,69 +1,253 @@
+use std::sync::Arc;
+
 use crate::{
     config::Config,
-    crawler,
-    storage::postgres::Storage,
+    crawler::{self, fetcher},
+    runs,
+    shutdown::Shutdown,
+    sinks::FileSinks,
+    storage::{ScrapeCheckpoint, ScrapeRunStats, Storage},
 };
-use tracing::{info, warn, error};
+use chrono::Utc;
+use serde_json::json;
+use tokio::time::Instant;
+use tracing::{info, info_span, warn, error, Instrument};
+
+/// What one run accumulates as it goes.
+struct RunState {
+    stats: ScrapeRunStats,
+    sinks: FileSinks,
+    traffic: fetcher::Traffic,
+}
 
 pub struct ScrapingService {
     cfg: Config,
-    storage: Storage,
+    storage: Arc<dyn Storage>,
 }
 
 impl ScrapingService {
-    pub async fn new(cfg: Config) -> anyhow::Result<Self> {
-        let storage = Storage::new(&cfg.database_url).await?;
-        Ok(Self { cfg, storage })
+    pub fn new(cfg: Config, storage: Arc<dyn Storage>) -> Self {
+        Self { cfg, storage }
     }
 
-    pub async fn run(&self) -> anyhow::Result<()> {
-        let mut total_saved = 0usize;
+    /// Crawl the configured page range, record the run in `scrape_runs`
+    /// and print its summary.
+    ///
+    /// Progress is checkpointed after every page; with `resume`, the last
+    /// unfinished run of this category continues, skipping the items it saved.
+    pub async fn run(&self, shutdown: &Shutdown, resume: bool) -> anyhow::Result<ScrapeRunStats> {
+        let category = self.cfg.crawler.base_url.as_str();
 
-        for page in self.cfg.start_page..=self.cfg.end_page {
-            info!(page, "Processing listing page");
+        let previous = if resume {
+            self.storage.find_unfinished_scrape_run(category).await?
+        } else {
+            None
+        };
+        let resumed = previous.is_some();
 
-            let links = match crawler::crawl_page_links(&self.cfg, page).await {
-                Ok(v) if !v.is_empty() => v,
-                Ok(_) => {
-                    info!(page, "No items on page");
-                    continue;
-                }
-                Err(e) => {
-                    warn!(page, error = %e, "Failed to crawl page links");
-                    continue;
+        let checkpoint = match previous {
+            Some(cp) => {
+                info!(
+                    run_id = cp.id,
+                    pages = format!("{}..={}", cp.start_page, cp.end_page),
+                    page = cp.page,
+                    saved_on_page = cp.saved_external_ids.len(),
+                    "Resuming unfinished scrape run"
+                );
+                cp
+            }
+            None => {
+                if resume {
+                    info!("No unfinished scrape run to resume, starting a new one");
                 }
-            };
 
-            info!(page, count = links.len(), "Found item links");
+                self.storage
+                    .begin_scrape_run(category, self.cfg.crawler.start_page as i32, self.cfg.crawler.end_page as i32)
+                    .await?
+            }
+        };
+
+        let config = json!({
+            "base_url": self.cfg.crawler.base_url,
+            "start_page": checkpoint.start_page,
+            "end_page": checkpoint.end_page,
+            "resume_from_page": resumed.then_some(checkpoint.page),
+            "delay_ms": self.cfg.crawler.page_delay_ms,
+            "sinks": self.cfg.output.sinks,
+        });
+
+        let audit_id = self
+            .storage
+            .record_scrape_run_start(checkpoint.id, resumed, &config)
+            .await?;
+
+        let started = Instant::now();
+
+        let run_label = format!("run-{}-{}", Utc::now().format("%Y%m%d-%H%M%S"), audit_id);
+        let mut run = RunState {
+            stats: ScrapeRunStats::default(),
+            sinks: FileSinks::new(&self.cfg.output, &run_label),
+            traffic: fetcher::Traffic::default(),
+        };
+
+        let result = self
+            .crawl(&checkpoint, shutdown, &mut run)
+            .instrument(info_span!("run", run_id = audit_id))
+            .await;
+
+        let RunState { mut stats, mut sinks, traffic } = run;
+
+        if let Err(e) = sinks.finish() {
+            error!(error = %e, "Failed to close output files");
+            stats.add_error("save", "file");
+        }
+
+        (stats.requests, stats.bytes_downloaded) = traffic.totals();
+
+        let status = match &result {
+            Err(_) => "failed",
+            Ok(()) if shutdown.is_requested() => "interrupted",
+            Ok(()) => "succeeded",
+        };
+
+        let elapsed = started.elapsed();
+        self.storage
+            .record_scrape_run_end(audit_id, status, &stats, elapsed.as_millis() as i64)
+            .await?;
+
+        runs::print_summary(audit_id, status, &stats, elapsed);
+
+        result?;
+
+        if status == "interrupted" {
+            warn!(run_id = checkpoint.id, total_saved = stats.items_saved(), "Scrape run interrupted, continue it with --resume");
+        } else {
+            self.storage.finish_scrape_run(checkpoint.id).await?;
+            info!(total_saved = stats.items_saved(), "DONE: all pages processed successfully");
+        }
+
+        Ok(stats)
+    }
+
+    async fn crawl(
+        &self,
+        checkpoint: &ScrapeCheckpoint,
+        shutdown: &Shutdown,
+        run: &mut RunState,
+    ) -> anyhow::Result<()> {
+        let mut already_saved = checkpoint.saved_external_ids.clone();
+
+        for page in checkpoint.page..=checkpoint.end_page {
+            if shutdown.is_requested() {
+                break;
+            }
+
+            self.crawl_page(checkpoint.id, page, std::mem::take(&mut already_saved), shutdown, run)
+                .instrument(info_span!("page", page))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Crawl and save one listing page, then checkpoint it: as done, or,
+    /// when interrupted, with the items saved so far. `already_saved`
+    /// are the items an earlier interrupted attempt at `page` saved.
+    async fn crawl_page(
+        &self,
+        run_id: i64,
+        page: i32,
+        mut already_saved: Vec<String>,
+        shutdown: &Shutdown,
+        run: &mut RunState,
+    ) -> anyhow::Result<()> {
+        let RunState { stats, sinks, traffic } = run;
+
+        info!("Processing listing page");
+        stats.pages_visited += 1;
+
+        let mut links = match crawler::crawl_page_links(&self.cfg, traffic, page as u32).await {
+            Ok(v) if !v.is_empty() => v,
+            Ok(_) => {
+                info!("No items on page");
+                return self.storage.save_scrape_checkpoint(run_id, page + 1, &[]).await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to crawl page links");
+                stats.add_error("page", fetcher::error_kind(&e));
+                return self.storage.save_scrape_checkpoint(run_id, page + 1, &[]).await;
+            }
+        };
+
+        stats.links_found += links.len();
+
+        // Items of an interrupted page that were already saved
+        if !already_saved.is_empty() {
+            links.retain(|link| !already_saved.iter().any(|id| id == crawler::item_id(link)));
+            info!(skipped = already_saved.len(), remaining = links.len(), "Skipping items saved before interruption");
+        }
+
+        info!(count = links.len(), "Found item links");
+
+        let crawled = crawler::crawl_details(&self.cfg, traffic, &links, shutdown).await;
 
-            let houses = match crawler::crawl_details(&links).await {
-                Ok(v) if !v.is_empty() => v,
-                Ok(_) => {
-                    warn!(page, "No house details extracted");
-                    continue;
+        for (external_id, kind, e) in &crawled.failures {
+            warn!(external_id = %external_id, error = %e, "Failed to crawl house details");
+            stats.add_error("item", kind);
+        }
+        stats.items_failed += crawled.failures.len();
+
+        let houses = crawled.houses;
+
+        if houses.is_empty() {
+            warn!("No house details extracted");
+            if !shutdown.is_requested() {
+                self.storage.save_scrape_checkpoint(run_id, page + 1, &[]).await?;
+            }
+            return Ok(());
+        }
+
+        if self.cfg.output.database() {
+            match self.storage.save_houses_bulk(&houses).await {
+                Ok(saved) => {
+                    stats.items_new += saved.new;
+                    stats.items_updated += saved.updated;
+                    stats.items_unchanged += saved.unchanged;
+                    info!(saved = saved.saved(), total_saved = stats.items_saved(), "Page saved successfully");
                 }
                 Err(e) => {
-                    error!(page, error = %e, "Failed to crawl house details");
-                    continue;
+                    error!(error = %e, "Failed to save page batch");
+                    stats.add_error("save", "db");
+                    stats.items_failed += houses.len();
                 }
-            };
+            }
+        }
 
-            match self.storage.save_houses_batch(&houses).await {
-                Ok(saved) => {
-                    total_saved += saved;
-                    info!(page, saved, total_saved, "Page saved successfully");
+        if !sinks.is_empty() {
+            match sinks.write(&houses) {
+                Ok(()) => {
+                    stats.items_written += houses.len();
+                    info!(written = houses.len(), total_written = stats.items_written, "Page written to output files");
                 }
                 Err(e) => {
-                    error!(page, error = %e, "Failed to save page batch");
+                    error!(error = format!("{:#}", e), "Failed to write page to output files");
+                    stats.add_error("save", "file");
                 }
             }
+        }
+
+        if shutdown.is_requested() {
+            already_saved.extend(houses.iter().map(|h| h.external_id.clone()));
+            return self.storage.save_scrape_checkpoint(run_id, page, &already_saved).await;
+        }
+
+        self.storage.save_scrape_checkpoint(run_id, page + 1, &[]).await?;
 
-            tokio::time::sleep(
-                std::time::Duration::from_millis(self.cfg.delay_ms)
-            ).await;
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_millis(self.cfg.crawler.page_delay_ms)) => {}
+            _ = shutdown.requested() => {}
         }
 
-        info!(total_saved, "DONE: all pages processed successfully");
         Ok(())
     }
 }
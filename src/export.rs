@@ -0,0 +1,119 @@
+//! The `export` command: one row per listing, with its phones, images,
+//! features and price history inline, as CSV, JSONL or Parquet.
+//!
+//! Columns are those of the nested file sink layout plus `deleted_at`.
+//! Listings are streamed from Postgres and written a chunk at a time,
+//! so the size of an export is bounded by the disk, not the memory.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use futures::StreamExt;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::sinks::tables::{self, Table};
+use crate::sinks::{self, Format};
+use crate::storage::postgres::{ExportFilter, PgStorage};
+
+/// Rows handed to the writer at a time.
+const CHUNK_ROWS: usize = 1_000;
+
+/// Write the listings matching `filter` to `path`. The format is
+/// `format` or else the extension of `path`; `columns` picks and orders
+/// the columns, all of them when empty.
+pub async fn run(
+    storage: &PgStorage,
+    filter: &ExportFilter,
+    path: &Path,
+    format: Option<&str>,
+    columns: &[String],
+) -> Result<()> {
+    let format_name = match format {
+        Some(f) => f,
+        None => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .context("no --format given and the file has no extension")?,
+    };
+    let Some(format) = Format::parse(format_name) else {
+        bail!("unknown export format {:?}; expected csv, jsonl or parquet", format_name);
+    };
+
+    if filter.category.is_some() {
+        let uncategorized = storage.count_uncategorized_houses().await?;
+        if uncategorized > 0 {
+            warn!(count = uncategorized, "Listings without a recorded category are left out by --category");
+        }
+    }
+
+    let schema = select_columns(columns)?;
+    let mut writer = sinks::create(format, path, &schema)?;
+
+    let mut rows = storage.export_houses(filter);
+    let mut chunk = Vec::with_capacity(CHUNK_ROWS);
+    let mut total = 0;
+
+    while let Some(row) = rows.next().await {
+        chunk.push(project(&row?, &schema));
+
+        if chunk.len() == CHUNK_ROWS {
+            writer.write(&chunk)?;
+            total += chunk.len();
+            chunk.clear();
+        }
+    }
+
+    if !chunk.is_empty() {
+        writer.write(&chunk)?;
+        total += chunk.len();
+    }
+
+    writer.finish()?;
+
+    eprintln!("Exported {} listings to {}", total, path.display());
+    Ok(())
+}
+
+/// The export schema, narrowed to `columns` in their order.
+fn select_columns(columns: &[String]) -> Result<SchemaRef> {
+    let houses = tables::schema(Table::Houses, true);
+
+    let mut all: Vec<Field> = houses.fields().iter().map(|f| f.as_ref().clone()).collect();
+    all.push(Field::new("deleted_at", DataType::Utf8, true));
+
+    if columns.is_empty() {
+        return Ok(Arc::new(Schema::new(all)));
+    }
+
+    let mut selected: Vec<Field> = Vec::with_capacity(columns.len());
+
+    for name in columns {
+        if selected.iter().any(|f| f.name() == name) {
+            bail!("column {:?} is selected twice", name);
+        }
+
+        match all.iter().find(|f| f.name() == name) {
+            Some(field) => selected.push(field.clone()),
+            None => {
+                let known: Vec<&str> = all.iter().map(|f| f.name().as_str()).collect();
+                bail!("unknown column {:?}; expected one of {}", name, known.join(", "));
+            }
+        }
+    }
+
+    Ok(Arc::new(Schema::new(selected)))
+}
+
+/// The columns of `schema` from an exported row.
+fn project(row: &Value, schema: &Schema) -> Value {
+    let out: Map<String, Value> = schema
+        .fields()
+        .iter()
+        .map(|f| (f.name().clone(), row.get(f.name()).cloned().unwrap_or(Value::Null)))
+        .collect();
+
+    Value::Object(out)
+}
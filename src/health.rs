@@ -0,0 +1,125 @@
+//! `/healthz` and `/readyz`, served next to `/metrics`.
+//!
+//! `/healthz` only says the process answers. `/readyz` also checks that
+//! the database is reachable, that every scheduled job last succeeded
+//! recently enough, and that list.am has not answered 429 lately.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::storage::postgres::PgStorage;
+
+/// How long after the last 429 the process counts as throttled.
+const THROTTLE_COOLDOWN_SECS: i64 = 600;
+
+/// Unix time of the last 429 from list.am, 0 when none was seen.
+static THROTTLED_AT: AtomicI64 = AtomicI64::new(0);
+
+/// Note the status of a list.am response (`None` when none came).
+pub fn record_status(status: Option<u16>) {
+    if status == Some(429) {
+        THROTTLED_AT.store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+}
+
+struct Health {
+    storage: PgStorage,
+    /// Scheduled jobs and their interval; empty outside `daemon`.
+    jobs: Vec<(&'static str, Duration)>,
+    jitter: Duration,
+    started_at: DateTime<Utc>,
+}
+
+pub fn router(storage: PgStorage, jobs: Vec<(&'static str, Duration)>, jitter: Duration) -> Router {
+    let state = Arc::new(Health {
+        storage,
+        jobs,
+        jitter,
+        started_at: Utc::now(),
+    });
+
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state)
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn readyz(State(health): State<Arc<Health>>) -> (StatusCode, Json<Value>) {
+    let mut ready = true;
+
+    let database = match tokio::time::timeout(Duration::from_secs(5), health.storage.ping()).await {
+        Ok(Ok(())) => json!({ "ok": true }),
+        Ok(Err(e)) => {
+            ready = false;
+            json!({ "ok": false, "error": format!("{:#}", e) })
+        }
+        Err(_) => {
+            ready = false;
+            json!({ "ok": false, "error": "timed out" })
+        }
+    };
+
+    let mut jobs = serde_json::Map::new();
+    for &(name, interval) in &health.jobs {
+        let check = job_check(&health, name, interval).await;
+        ready &= check["ok"] == true;
+        jobs.insert(name.to_string(), check);
+    }
+
+    let throttled_at = THROTTLED_AT.load(Ordering::Relaxed);
+    let throttled = throttled_at > 0 && Utc::now().timestamp() - throttled_at < THROTTLE_COOLDOWN_SECS;
+    ready &= !throttled;
+
+    let body = json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "checks": {
+            "database": database,
+            "jobs": jobs,
+            "throttle": {
+                "ok": !throttled,
+                "last_429_at": DateTime::from_timestamp(throttled_at, 0).filter(|_| throttled_at > 0),
+            },
+        },
+    });
+
+    let code = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(body))
+}
+
+/// A job is fine when its last outcome is a success no older than two
+/// intervals (plus jitter). Until that much time has passed since start,
+/// a missing or stale success is only pending.
+async fn job_check(health: &Health, name: &str, interval: Duration) -> Value {
+    let max_age = chrono::Duration::from_std(interval * 2 + health.jitter).unwrap_or(chrono::Duration::MAX);
+    let now = Utc::now();
+    let warming_up = now - health.started_at < max_age;
+
+    match health.storage.last_job_outcome(name).await {
+        Ok(Some((status, finished_at))) => {
+            let fresh = now - finished_at < max_age;
+            let ok = status == "succeeded" && (fresh || warming_up);
+
+            json!({
+                "ok": ok,
+                "last_status": status,
+                "finished_at": finished_at,
+                "max_age_s": max_age.num_seconds(),
+            })
+        }
+        Ok(None) => json!({
+            "ok": warming_up,
+            "last_status": null,
+            "max_age_s": max_age.num_seconds(),
+        }),
+        Err(e) => json!({ "ok": false, "error": format!("{:#}", e) }),
+    }
+}
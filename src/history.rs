@@ -0,0 +1,36 @@
+use crate::storage::postgres::PgStorage;
+
+/// Print the edit timeline of one listing, oldest version first.
+pub async fn print_timeline(storage: &PgStorage, external_id: &str) -> anyhow::Result<()> {
+    let versions = storage.fetch_house_versions(external_id).await?;
+
+    if versions.is_empty() {
+        println!("No versions recorded for item {}", external_id);
+        return Ok(());
+    }
+
+    println!("Edit timeline for item {}", external_id);
+
+    for v in &versions {
+        let when = v.recorded_at.format("%Y-%m-%d %H:%M:%S UTC");
+
+        if v.changed_fields.is_empty() {
+            println!("\nv{}  {}  first snapshot", v.version, when);
+            continue;
+        }
+
+        println!(
+            "\nv{}  {}  changed: {}",
+            v.version,
+            when,
+            v.changed_fields.join(", ")
+        );
+
+        for field in &v.changed_fields {
+            let change = &v.diff[field.as_str()];
+            println!("  {}: {} -> {}", field, change["old"], change["new"]);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+//! Log output. `log_format = "json"` writes one JSON object per event, with
+//! the fields of its enclosing spans (`job`, `run`, `page`, `item`)
+//! under `spans`, so one run or one listing can be filtered out of the
+//! log store.
+
+use anyhow::bail;
+
+/// Logs go to stderr, leaving stdout to command output (`item`, `runs`).
+pub fn init(format: &str) -> anyhow::Result<()> {
+    match format {
+        "text" => tracing_subscriber::fmt().with_writer(std::io::stderr).init(),
+        "json" => tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .init(),
+        other => bail!("log_format must be text or json, got {:?}", other),
+    }
+
+    Ok(())
+}
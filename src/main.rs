@@ -3,43 +3,218 @@ mod crawler;
 mod storage;
 mod scheduler;
 mod checker;
+mod cli;
+mod rate_limiter;
+mod bench;
+mod export;
+mod health;
+mod history;
+mod logging;
+mod metrics;
+mod migrate;
+mod runs;
+mod shutdown;
+mod sinks;
+mod stats;
 
-use std::env;
+use std::time::Duration;
+
+use anyhow::bail;
+
+use clap::Parser;
+use serde_json::{json, Value};
+use tracing::Instrument;
 
 use config::Config;
+use crawler::queue;
 use crawler::service::ScrapingService;
 use checker::service::RemovalCheckService;
-use storage::postgres::Storage;
+use cli::{Cli, Command, ConfigCommand, MigrateCommand};
+use shutdown::Shutdown;
+use storage::postgres::PgStorage;
+use storage::JobRun;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt::init();
 
-    let mode = env::args().nth(1).unwrap_or_else(|| "scraper".to_string());
+    let mut cfg = Config::load(cli.config.as_deref())?;
+    cli.command.apply(&mut cfg);
+    cfg.storage.auto_migrate |= cli.auto_migrate;
+    cfg.validate(cli.command.needs_database())?;
+
+    logging::init(&cfg.log_format)?;
+    rate_limiter::init_hosts(Duration::from_millis(cfg.http.host_delay_ms));
+
+    // SQLite databases are migrated when opened
+    let sqlite = storage::is_sqlite(&cfg.storage.database_url);
+
+    if sqlite && !cli.command.supports_sqlite() {
+        bail!("this command needs a Postgres database; SQLite only supports scrape, check, explain, item and config");
+    }
+
+    if cli.command.needs_schema() && !sqlite {
+        let storage = PgStorage::new(&cfg.storage.database_url).await?;
+        storage.ensure_schema(cfg.storage.auto_migrate).await?;
+    }
+
+    let lock_wait = Duration::from_secs(cfg.storage.lock_wait_secs);
+
+    if let Some(addr) = cfg.metrics_addr
+        && cli.command.is_long_running()
+    {
+        // Job freshness only means something for the scheduled jobs
+        let jobs = if matches!(cli.command, Command::Daemon) {
+            scheduler::intervals(&cfg)?
+        } else {
+            Vec::new()
+        };
+
+        // The readiness checks read Postgres, an SQLite run only exposes /metrics
+        let health = if sqlite {
+            axum::Router::new()
+        } else {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            health::router(storage, jobs, Duration::from_secs(cfg.scheduler.jitter_secs))
+        };
+        metrics::serve(addr, health).await?;
+    }
+
+    match cli.command {
+        Command::Scrape { resume, .. } => {
+            let Some(lock) = storage::lock_job(&cfg.storage.database_url, "scrape", lock_wait).await? else {
+                return Ok(());
+            };
+
+            let shutdown = Shutdown::listen();
+            let job_run = storage::start_job_run(&cfg.storage.database_url, "scrape").await?;
+
+            let storage = storage::open(&cfg.storage.database_url).await?;
+            let service = ScrapingService::new(cfg, storage);
+            let result = service
+                .run(&shutdown, resume)
+                .instrument(scheduler::job_span("scrape", job_run.id()))
+                .await;
+
+            finish_job_run(job_run, &shutdown, result.as_ref().map(|stats| stats.counters())).await?;
+            result?;
+            lock.release().await?;
+        }
+
+        Command::Check { dry_run, output } => {
+            let storage = storage::open(&cfg.storage.database_url).await?;
+            let checker = RemovalCheckService::new(storage, &cfg);
+
+            if dry_run {
+                checker.dry_run(output.as_deref()).await?;
+            } else {
+                let Some(lock) = storage::lock_job(&cfg.storage.database_url, "check", lock_wait).await? else {
+                    return Ok(());
+                };
+
+                let shutdown = Shutdown::listen();
+                let job_run = storage::start_job_run(&cfg.storage.database_url, "check").await?;
+
+                let result = checker
+                    .run(&shutdown)
+                    .instrument(scheduler::job_span("check", job_run.id()))
+                    .await;
+
+                finish_job_run(job_run, &shutdown, result.as_ref().map(|stats| stats.counters())).await?;
+                result?;
+                lock.release().await?;
+            }
+        }
 
-    let cfg = Config::from_env()?;
+        Command::Discover { .. } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            let Some(lock) = storage.lock_job("discover", lock_wait).await? else {
+                return Ok(());
+            };
 
-    match mode.as_str() {
-        "scraper" => {
-            let service = ScrapingService::new(cfg).await?;
-            service.run().await?;
+            queue::discover(&cfg, &storage, &Shutdown::listen()).await?;
+            lock.release().await?;
         }
 
-        "checker" => {
-            let storage = Storage::new(&cfg.database_url).await?;
-            let checker = RemovalCheckService::new(storage);
-            checker.run().await?;
+        Command::Worker { drain } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            queue::work(&cfg, &storage, &Shutdown::listen(), drain).await?;
         }
 
-        _ => {
-            eprintln!(
-                "Unknown mode: {}\nUsage: <binary> [scraper|checker]",
-                mode
-            );
-            std::process::exit(1);
+        Command::Daemon => {
+            scheduler::run(cfg, Shutdown::listen()).await?;
+        }
+
+        Command::Item { url_or_id, save } => {
+            let house = crawler::inspect_item(&cfg, &url_or_id).await?;
+            println!("{}", serde_json::to_string_pretty(&house)?);
+
+            if save {
+                let storage = storage::open(&cfg.storage.database_url).await?;
+                let id = storage.save_house(&house).await?;
+                eprintln!("Saved {} as house {}", house.external_id, id);
+            }
+        }
+
+        Command::Explain { external_id } => {
+            let storage = storage::open(&cfg.storage.database_url).await?;
+            let checker = RemovalCheckService::new(storage, &cfg);
+            checker.explain(&external_id).await?;
+        }
+
+        Command::History { external_id } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            history::print_timeline(&storage, &external_id).await?;
+        }
+
+        Command::Runs { limit } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            runs::print_recent(&storage, limit).await?;
+        }
+
+        Command::Stats => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            stats::print_overview(&storage).await?;
+        }
+
+        Command::Export { file, format, columns, filter } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            export::run(&storage, &filter.to_filter(), &file, format.as_deref(), &columns).await?;
+        }
+
+        Command::Migrate { command } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+
+            match command {
+                MigrateCommand::Run => migrate::run(&storage).await?,
+                MigrateCommand::Status => migrate::print_status(&storage).await?,
+                MigrateCommand::Check => migrate::check(&storage).await?,
+            }
+        }
+
+        Command::Config { command: ConfigCommand::Show } => {
+            print!("{}", cfg.to_masked_toml()?);
+        }
+
+        Command::BenchStorage { count } => {
+            let storage = PgStorage::new(&cfg.storage.database_url).await?;
+            bench::run_storage_bench(&storage, count).await?;
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Close the `job_runs` row of a standalone `scrape` / `check` the way
+/// the daemon closes its runs.
+async fn finish_job_run(job_run: JobRun, shutdown: &Shutdown, outcome: Result<Value, &anyhow::Error>) -> anyhow::Result<()> {
+    match outcome {
+        Ok(counters) => {
+            let status = if shutdown.is_requested() { "interrupted" } else { "succeeded" };
+            job_run.finish(status, &counters, None).await
+        }
+        Err(e) => job_run.finish("failed", &json!({}), Some(&format!("{:#}", e))).await,
+    }
+}
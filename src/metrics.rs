@@ -0,0 +1,152 @@
+//! Prometheus metrics, served on `/metrics` (next to the health checks)
+//! when `METRICS_ADDR` is set.
+
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use axum::{http::header, routing::get, Router};
+use prometheus::{
+    exponential_buckets, histogram_opts, opts, Encoder, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, Registry, TextEncoder,
+};
+use tracing::{error, info};
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        opts!("listam_http_requests_total", "Requests to list.am by endpoint kind and status"),
+        &["endpoint", "status"],
+    ))
+});
+
+static HTTP_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        histogram_opts!(
+            "listam_http_request_duration_seconds",
+            "Latency of requests to list.am by endpoint kind",
+            exponential_buckets(0.05, 2.0, 10).unwrap()
+        ),
+        &["endpoint"],
+    ))
+});
+
+static RETRIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        opts!("listam_retries_total", "Retried operations by kind"),
+        &["kind"],
+    ))
+});
+
+static PARSE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        opts!("listam_parse_failures_total", "Item page fields missing or unparseable"),
+        &["field"],
+    ))
+});
+
+static DB_UPSERT_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        histogram_opts!(
+            "listam_db_upsert_duration_seconds",
+            "Duration of one batch save",
+            exponential_buckets(0.005, 2.0, 12).unwrap()
+        ),
+        &["path"],
+    ))
+});
+
+static ITEMS_SAVED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        opts!("listam_items_saved_total", "Listings saved by outcome"),
+        &["outcome"],
+    ))
+});
+
+static HOUSES_DELETED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new(
+        "listam_houses_marked_deleted_total",
+        "Listings marked deleted by the removal checker",
+    ))
+});
+
+static RATE_LIMIT_WAIT: LazyLock<Histogram> = LazyLock::new(|| {
+    register(Histogram::with_opts(histogram_opts!(
+        "listam_rate_limiter_wait_seconds",
+        "Time spent waiting for a rate limiter slot",
+        exponential_buckets(0.01, 2.0, 12).unwrap()
+    )))
+});
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(metric: prometheus::Result<T>) -> T {
+    let metric = metric.expect("invalid metric definition");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+/// `status` is the HTTP status code, or `error` when no response came.
+pub fn record_request(endpoint: &str, status: Option<u16>, elapsed: Duration) {
+    let status = status.map(|s| s.to_string()).unwrap_or_else(|| "error".to_string());
+
+    HTTP_REQUESTS.with_label_values(&[endpoint, &status]).inc();
+    HTTP_DURATION
+        .with_label_values(&[endpoint])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn record_retry(kind: &str) {
+    RETRIES.with_label_values(&[kind]).inc();
+}
+
+pub fn record_parse_failure(field: &str) {
+    PARSE_FAILURES.with_label_values(&[field]).inc();
+}
+
+pub fn record_upsert(path: &str, elapsed: Duration) {
+    DB_UPSERT_DURATION
+        .with_label_values(&[path])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn record_saved(outcome: &str, count: usize) {
+    ITEMS_SAVED.with_label_values(&[outcome]).inc_by(count as u64);
+}
+
+pub fn record_deleted(count: usize) {
+    HOUSES_DELETED.inc_by(count as u64);
+}
+
+pub fn record_rate_limit_wait(waited: Duration) {
+    RATE_LIMIT_WAIT.observe(waited.as_secs_f64());
+}
+
+async fn metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    let mut buf = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buf)
+        .expect("failed to encode metrics");
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        String::from_utf8(buf).unwrap_or_default(),
+    )
+}
+
+/// Serve `/metrics` and the `health` routes on `addr` in the background.
+pub async fn serve(addr: SocketAddr, health: Router) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let app = Router::new().route("/metrics", get(metrics)).merge(health);
+
+    info!(%addr, "Serving metrics and health checks");
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            error!(error = %e, "Metrics server stopped");
+        }
+    });
+
+    Ok(())
+}
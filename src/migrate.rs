@@ -0,0 +1,40 @@
+use crate::storage::postgres::{MigrationState, PgStorage};
+
+/// Print every migration with its state, oldest first.
+pub async fn print_status(storage: &PgStorage) -> anyhow::Result<()> {
+    for s in storage.migration_status().await? {
+        let state = match s.state {
+            MigrationState::Applied(at) => format!("applied {}", at.format("%Y-%m-%d %H:%M:%S")),
+            MigrationState::Pending => "pending".to_string(),
+            MigrationState::Modified => "modified since applied".to_string(),
+            MigrationState::Failed => "failed".to_string(),
+            MigrationState::Unknown => "applied, unknown to this binary".to_string(),
+        };
+
+        println!("{:>14}  {:<40}  {}", s.version, s.description, state);
+    }
+
+    Ok(())
+}
+
+pub async fn run(storage: &PgStorage) -> anyhow::Result<()> {
+    let applied = storage.run_migrations().await?;
+
+    if applied.is_empty() {
+        println!("Database is up to date");
+    } else {
+        for version in &applied {
+            println!("Applied {}", version);
+        }
+    }
+
+    Ok(())
+}
+
+/// Exit with an error when migrations are missing.
+pub async fn check(storage: &PgStorage) -> anyhow::Result<()> {
+    storage.ensure_schema(false).await?;
+    println!("Database is up to date");
+
+    Ok(())
+}
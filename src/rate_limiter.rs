@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Duration, Instant};
+
+use crate::metrics;
+
+/// Spaces requests at least `min_interval` apart, however many tasks
+/// share it. Clones share the same schedule.
+#[derive(Clone)]
+pub struct RateLimiter {
+    min_interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Wait for the next free slot.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.min_interval;
+            slot
+        };
+
+        metrics::record_rate_limit_wait(slot.saturating_duration_since(Instant::now()));
+        sleep_until(slot).await;
+    }
+}
+
+/// Spacing of [`acquire_host`]; unset (e.g. in tests) does not wait.
+static HOST_INTERVAL: OnceLock<Duration> = OnceLock::new();
+
+/// One limiter per host, shared by every job and task of the process.
+static HOSTS: OnceLock<std::sync::Mutex<HashMap<String, RateLimiter>>> = OnceLock::new();
+
+/// Set the spacing of requests to one host (`http.host_delay_ms`).
+/// Only the first call takes effect.
+pub fn init_hosts(min_interval: Duration) {
+    let _ = HOST_INTERVAL.set(min_interval);
+}
+
+/// Wait for the next free slot of the host of `url`. URLs without a host
+/// are not limited; sending them fails anyway.
+pub async fn acquire_host(url: &str) {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) else {
+        return;
+    };
+
+    let limiter = HOSTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(host)
+        .or_insert_with(|| RateLimiter::new(HOST_INTERVAL.get().copied().unwrap_or_default()))
+        .clone();
+
+    limiter.acquire().await;
+}
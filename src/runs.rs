@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::storage::postgres::PgStorage;
+use crate::storage::ScrapeRunStats;
+
+/// Print the end-of-run report of a scrape run.
+pub fn print_summary(run_id: i64, status: &str, stats: &ScrapeRunStats, elapsed: Duration) {
+    println!("\nScrape run {} {} in {}", run_id, status, fmt_duration(elapsed.as_millis() as i64));
+    println!("  pages visited:   {}", stats.pages_visited);
+    println!("  links found:     {}", stats.links_found);
+    println!(
+        "  items:           {} new, {} updated, {} unchanged, {} failed",
+        stats.items_new, stats.items_updated, stats.items_unchanged, stats.items_failed
+    );
+    if stats.items_written > 0 {
+        println!("  files:           {} listings written", stats.items_written);
+    }
+    println!(
+        "  traffic:         {} requests, {}",
+        stats.requests,
+        fmt_bytes(stats.bytes_downloaded as i64)
+    );
+
+    if stats.errors.is_empty() {
+        println!("  errors:          none");
+    } else {
+        println!("  errors:");
+        for (kind, count) in &stats.errors {
+            println!("    {:<20} {}", kind, count);
+        }
+    }
+}
+
+/// Print the most recent scrape runs, newest first.
+pub async fn print_recent(storage: &PgStorage, limit: i64) -> anyhow::Result<()> {
+    let runs = storage.fetch_scrape_runs(limit).await?;
+
+    if runs.is_empty() {
+        println!("No scrape runs recorded");
+        return Ok(());
+    }
+
+    println!(
+        "{:>6}  {:<19}  {:<11}  {:>9}  {:>5}  {:>6}  {:>5}  {:>5}  {:>5}  {:>5}  {:>8}  {:>9}  errors",
+        "id", "started (UTC)", "status", "duration", "pages", "links", "new", "upd", "same", "fail", "requests", "data"
+    );
+
+    for r in &runs {
+        let errors = r
+            .errors
+            .as_object()
+            .map(|m| {
+                m.iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+
+        let status = if r.resumed {
+            format!("{}*", r.status)
+        } else {
+            r.status.clone()
+        };
+
+        println!(
+            "{:>6}  {:<19}  {:<11}  {:>9}  {:>5}  {:>6}  {:>5}  {:>5}  {:>5}  {:>5}  {:>8}  {:>9}  {}",
+            r.id,
+            r.started_at.format("%Y-%m-%d %H:%M:%S"),
+            status,
+            r.duration_ms.map(fmt_duration).unwrap_or_else(|| "-".to_string()),
+            r.pages_visited,
+            r.links_found,
+            r.items_new,
+            r.items_updated,
+            r.items_unchanged,
+            r.items_failed,
+            r.requests,
+            fmt_bytes(r.bytes_downloaded),
+            errors
+        );
+    }
+
+    println!("\n* resumed run");
+
+    Ok(())
+}
+
+fn fmt_duration(ms: i64) -> String {
+    let s = ms / 1000;
+    if s >= 3600 {
+        format!("{}h{:02}m", s / 3600, s / 60 % 60)
+    } else if s >= 60 {
+        format!("{}m{:02}s", s / 60, s % 60)
+    } else {
+        format!("{}.{}s", s, ms % 1000 / 100)
+    }
+}
+
+fn fmt_bytes(bytes: i64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
@@ -1,4 +1,222 @@
-// Placeholder for cron / periodic scheduling logic
-pub fn run() {
-    // TODO
-}
\ No newline at end of file
+//! `daemon` mode: runs every job on its cron schedule and records each
+//! run in `job_runs`.
+//!
+//! A job never overlaps itself: its loop awaits the run before looking
+//! for the next tick, so ticks missed by a long run are skipped. Runs
+//! also take the job's advisory lock, so a `scraper` / `checker`
+//! started by hand or a second daemon makes them skip instead.
+
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use cron::Schedule;
+use serde_json::{json, Value};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{error, info, info_span, warn, Instrument, Span};
+
+use crate::checker::service::RemovalCheckService;
+use crate::config::{self, Config};
+use crate::crawler::images;
+use crate::crawler::service::ScrapingService;
+use crate::shutdown::Shutdown;
+use crate::storage::postgres::PgStorage;
+
+/// Days recomputed by each `aggregates` run, so late deletions are
+/// reflected in the recent history.
+const AGGREGATE_DAYS: i32 = 7;
+
+#[derive(Debug, Clone, Copy)]
+enum Job {
+    Scrape,
+    Check,
+    Images,
+    Aggregates,
+}
+
+impl Job {
+    const ALL: [Job; 4] = [Job::Scrape, Job::Check, Job::Images, Job::Aggregates];
+
+    fn name(self) -> &'static str {
+        match self {
+            Job::Scrape => "scrape",
+            Job::Check => "check",
+            Job::Images => "images",
+            Job::Aggregates => "aggregates",
+        }
+    }
+
+    fn schedule(self, cfg: &Config) -> &str {
+        match self {
+            Job::Scrape => &cfg.scheduler.scrape,
+            Job::Check => &cfg.scheduler.check,
+            Job::Images => &cfg.scheduler.images,
+            Job::Aggregates => &cfg.scheduler.aggregates,
+        }
+    }
+
+    /// Run the job once and return its counters.
+    async fn execute(self, cfg: &Config, storage: &PgStorage, shutdown: &Shutdown) -> anyhow::Result<Value> {
+        match self {
+            Job::Scrape => {
+                // An interrupted scheduled scrape is picked up by the next one
+                let stats = ScrapingService::new(cfg.clone(), Arc::new(storage.clone()))
+                    .run(shutdown, true)
+                    .await?;
+                Ok(stats.counters())
+            }
+            Job::Check => {
+                let stats = RemovalCheckService::new(Arc::new(storage.clone()), cfg)
+                    .run(shutdown)
+                    .await?;
+                Ok(stats.counters())
+            }
+            Job::Images => {
+                let stats = images::download_pending(storage, &cfg.http, &cfg.images).await?;
+                Ok(json!({
+                    "downloaded": stats.downloaded,
+                    "existing": stats.existing,
+                    "failed": stats.failed,
+                    "bytes": stats.bytes,
+                }))
+            }
+            Job::Aggregates => {
+                let days = storage.refresh_daily_stats(AGGREGATE_DAYS).await?;
+                Ok(json!({ "days": days }))
+            }
+        }
+    }
+}
+
+/// Run the schedules until shutdown is requested; a job in progress
+/// stops at its next safe point first.
+pub async fn run(cfg: Config, shutdown: Shutdown) -> anyhow::Result<()> {
+    let storage = PgStorage::new(&cfg.storage.database_url).await?;
+    let mut jobs = JoinSet::new();
+
+    for job in Job::ALL {
+        let Some(schedule) = parse_schedule(job, &cfg)? else {
+            info!(job = job.name(), "Job disabled");
+            continue;
+        };
+
+        info!(job = job.name(), schedule = job.schedule(&cfg).trim(), "Job scheduled");
+        jobs.spawn(job_loop(job, schedule, cfg.clone(), storage.clone(), shutdown.clone()));
+    }
+
+    if jobs.is_empty() {
+        anyhow::bail!("all jobs are disabled");
+    }
+
+    // The loops return on shutdown, or when a schedule has no upcoming
+    // tick left
+    while let Some(res) = jobs.join_next().await {
+        res?;
+    }
+
+    Ok(())
+}
+
+/// Name and interval between two upcoming ticks of every enabled job.
+pub fn intervals(cfg: &Config) -> anyhow::Result<Vec<(&'static str, Duration)>> {
+    let mut intervals = Vec::new();
+
+    for job in Job::ALL {
+        let Some(schedule) = parse_schedule(job, cfg)? else {
+            continue;
+        };
+
+        let mut ticks = schedule.upcoming(Utc);
+        if let (Some(a), Some(b)) = (ticks.next(), ticks.next()) {
+            intervals.push((job.name(), (b - a).to_std().unwrap_or_default()));
+        }
+    }
+
+    Ok(intervals)
+}
+
+/// `None` when the job is disabled.
+fn parse_schedule(job: Job, cfg: &Config) -> anyhow::Result<Option<Schedule>> {
+    let expr = job.schedule(cfg);
+
+    config::parse_schedule(expr).map_err(|e| anyhow::anyhow!("invalid schedule for {}: {:?}: {}", job.name(), expr, e))
+}
+
+async fn job_loop(job: Job, schedule: Schedule, cfg: Config, storage: PgStorage, shutdown: Shutdown) {
+    let max_jitter = cfg.scheduler.jitter_secs;
+
+    while let Some(next) = schedule.upcoming(Utc).next() {
+        let jitter = Duration::from_secs(rand::random_range(0..=max_jitter));
+        let delay = (next - Utc::now()).to_std().unwrap_or_default() + jitter;
+
+        info!(job = job.name(), next = %next, jitter_s = jitter.as_secs(), "Waiting for next run");
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = shutdown.requested() => return,
+        }
+
+        if let Err(e) = run_once(job, &cfg, &storage, &shutdown).await {
+            error!(job = job.name(), error = %e, "Failed to record job run");
+        }
+
+        if shutdown.is_requested() {
+            return;
+        }
+
+        let missed = schedule.after(&next).take_while(|t| *t < Utc::now()).count();
+        if missed > 0 {
+            warn!(job = job.name(), missed, "Run outlasted its interval, skipping missed ticks");
+        }
+    }
+
+    warn!(job = job.name(), "Schedule has no upcoming runs");
+}
+
+/// The span every event of a job run is logged in, by the daemon and by
+/// the standalone `scraper` / `checker`. `run_id` is the `job_runs` id,
+/// `None` on SQLite.
+pub fn job_span(job: &str, run_id: Option<i64>) -> Span {
+    info_span!("job", job, job_run_id = run_id)
+}
+
+async fn run_once(job: Job, cfg: &Config, storage: &PgStorage, shutdown: &Shutdown) -> anyhow::Result<()> {
+    let wait = Duration::from_secs(cfg.storage.lock_wait_secs);
+    let lock = storage.lock_job(job.name(), wait).await?;
+    let run_id = storage.start_job_run(job.name()).await?;
+
+    let Some(lock) = lock else {
+        storage
+            .finish_job_run(run_id, "skipped", &json!({}), Some("held by another instance"))
+            .await?;
+        return Ok(());
+    };
+
+    let started = Instant::now();
+
+    info!(job = job.name(), run_id, "Job started");
+
+    match job.execute(cfg, storage, shutdown).instrument(job_span(job.name(), Some(run_id))).await {
+        Ok(counters) => {
+            let status = if shutdown.is_requested() { "interrupted" } else { "succeeded" };
+
+            info!(
+                job = job.name(),
+                run_id,
+                status,
+                elapsed_s = started.elapsed().as_secs(),
+                counters = %counters,
+                "Job finished"
+            );
+            storage.finish_job_run(run_id, status, &counters, None).await?;
+        }
+        Err(e) => {
+            error!(job = job.name(), run_id, error = %e, "Job failed");
+            storage
+                .finish_job_run(run_id, "failed", &json!({}), Some(&format!("{:#}", e)))
+                .await?;
+        }
+    }
+
+    lock.release().await
+}
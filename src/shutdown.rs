@@ -0,0 +1,50 @@
+//! SIGINT / SIGTERM handling. The first signal asks running work to stop
+//! at its next safe point; a second one exits at once.
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::warn;
+
+#[derive(Clone)]
+pub struct Shutdown {
+    rx: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Install the signal handlers. Call once, from the modes that check
+    /// for shutdown; the others keep the default Ctrl-C behavior.
+    pub fn listen() -> Self {
+        let (tx, rx) = watch::channel(false);
+
+        tokio::spawn(async move {
+            let mut term = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+
+            let mut wait = async || {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                    _ = term.recv() => "SIGTERM",
+                }
+            };
+
+            let name = wait().await;
+            warn!(signal = name, "Shutdown requested, finishing current work (send again to exit now)");
+            let _ = tx.send(true);
+
+            let name = wait().await;
+            warn!(signal = name, "Exiting immediately");
+            std::process::exit(130);
+        });
+
+        Self { rx }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolves once shutdown has been requested.
+    pub async fn requested(&self) {
+        let mut rx = self.rx.clone();
+        let _ = rx.wait_for(|v| *v).await;
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use anyhow::Result;
+use arrow_schema::SchemaRef;
+use serde_json::Value;
+
+use super::TableWriter;
+
+/// Appended CSV with the schema's columns; the header is written once,
+/// when the file is new. Lists are written as JSON.
+pub struct CsvWriter {
+    out: csv::Writer<std::fs::File>,
+    columns: Vec<String>,
+}
+
+impl CsvWriter {
+    pub fn open(path: &Path, schema: &SchemaRef) -> Result<Self> {
+        let is_new = !path.exists() || path.metadata()?.len() == 0;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        let columns: Vec<String> = schema.fields().iter().map(|f| f.name().clone()).collect();
+        let mut out = csv::Writer::from_writer(file);
+
+        if is_new {
+            out.write_record(&columns)?;
+        }
+
+        Ok(Self { out, columns })
+    }
+}
+
+impl TableWriter for CsvWriter {
+    fn write(&mut self, rows: &[Value]) -> Result<()> {
+        for row in rows {
+            self.out.write_record(self.columns.iter().map(|c| cell(&row[c.as_str()])))?;
+        }
+        Ok(self.out.flush()?)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,202 @@
+//! File sinks: the listings of each scrape run as JSONL, CSV or Parquet
+//! files under `output.dir`, next to or instead of the database.
+//!
+//! Files are grouped in a directory per run (`run-<start>-<id>/`) or per
+//! UTC day (`2026-10-19/`), one file per table. Daily JSONL and CSV
+//! files are appended to by every run of the day; Parquet files cannot
+//! be, so each run writes its own (`houses-<run>.parquet`).
+//!
+//! The same writers produce the files of the `export` command.
+
+mod csv;
+mod jsonl;
+mod parquet;
+pub mod tables;
+
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use arrow_schema::SchemaRef;
+use chrono::{SecondsFormat, Utc};
+use serde_json::Value;
+
+use crate::config::OutputConfig;
+use crate::crawler::models::HouseDetails;
+use tables::Table;
+
+/// One open file of a sink.
+pub trait TableWriter: Send {
+    fn write(&mut self, rows: &[Value]) -> Result<()>;
+
+    /// Flush and close the file.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "jsonl" => Some(Format::Jsonl),
+            "csv" => Some(Format::Csv),
+            "parquet" => Some(Format::Parquet),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::Csv => "csv",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// The file sinks of one scrape run.
+pub struct FileSinks {
+    sinks: Vec<FileSink>,
+}
+
+impl FileSinks {
+    /// The file sinks of `output.sinks` (`database` is not one), for the
+    /// run labelled `run`. Nothing is created before the first write.
+    pub fn new(cfg: &OutputConfig, run: &str) -> Self {
+        let sinks = cfg
+            .sinks
+            .iter()
+            .filter_map(|name| Format::parse(name))
+            .map(|format| FileSink {
+                format,
+                root: PathBuf::from(&cfg.dir),
+                by_date: cfg.rotate == "date",
+                nested: cfg.layout == "nested",
+                run: run.to_string(),
+                dir: None,
+                writers: BTreeMap::new(),
+            })
+            .collect();
+
+        Self { sinks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn write(&mut self, houses: &[HouseDetails]) -> Result<()> {
+        for sink in &mut self.sinks {
+            sink.write(houses)
+                .with_context(|| format!("failed to write {} output", sink.format.extension()))?;
+        }
+        Ok(())
+    }
+
+    /// Close every open file. Parquet files are unreadable until then.
+    pub fn finish(&mut self) -> Result<()> {
+        for sink in &mut self.sinks {
+            sink.finish()?;
+        }
+        Ok(())
+    }
+}
+
+struct FileSink {
+    format: Format,
+    root: PathBuf,
+    by_date: bool,
+    nested: bool,
+    run: String,
+    /// Directory of the open files.
+    dir: Option<PathBuf>,
+    writers: BTreeMap<Table, Box<dyn TableWriter>>,
+}
+
+impl FileSink {
+    fn write(&mut self, houses: &[HouseDetails]) -> Result<()> {
+        let now = Utc::now();
+
+        let dir = if self.by_date {
+            self.root.join(now.format("%Y-%m-%d").to_string())
+        } else {
+            self.root.join(&self.run)
+        };
+
+        // A new day (or the first write) rotates to a new directory
+        if self.dir.as_ref() != Some(&dir) {
+            self.finish()?;
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            self.dir = Some(dir);
+        }
+
+        let scraped_at = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        for (table, rows) in tables::rows(houses, self.nested, &scraped_at) {
+            if rows.is_empty() {
+                continue;
+            }
+
+            let writer = match self.writers.entry(table) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    let dir = self.dir.as_deref().expect("directory opened above");
+                    e.insert(open(self.format, dir, table, self.nested, self.by_date.then_some(&self.run))?)
+                }
+            };
+
+            writer.write(&rows)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        for (_, writer) in std::mem::take(&mut self.writers) {
+            writer.finish()?;
+        }
+        self.dir = None;
+        Ok(())
+    }
+}
+
+/// Open the file of `table` in `dir`. A daily Parquet file is named
+/// after the `run` too, since it cannot be appended to.
+fn open(
+    format: Format,
+    dir: &Path,
+    table: Table,
+    nested: bool,
+    run: Option<&String>,
+) -> Result<Box<dyn TableWriter>> {
+    let schema = tables::schema(table, nested);
+
+    let name = match (format, run) {
+        (Format::Parquet, Some(run)) => format!("{}-{}.parquet", table.name(), run),
+        _ => format!("{}.{}", table.name(), format.extension()),
+    };
+
+    writer(format, &dir.join(name), &schema)
+}
+
+/// Create `path` afresh as a `format` file of `schema`'s columns.
+pub fn create(format: Format, path: &Path, schema: &SchemaRef) -> Result<Box<dyn TableWriter>> {
+    // The JSONL and CSV writers append, to an empty file here
+    File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    writer(format, path, schema)
+}
+
+fn writer(format: Format, path: &Path, schema: &SchemaRef) -> Result<Box<dyn TableWriter>> {
+    Ok(match format {
+        Format::Jsonl => Box::new(jsonl::JsonlWriter::open(path)?),
+        Format::Csv => Box::new(csv::CsvWriter::open(path, schema)?),
+        Format::Parquet => Box::new(parquet::ParquetWriter::open(path, schema)?),
+    })
+}
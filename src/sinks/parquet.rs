@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use arrow_json::reader::{Decoder, ReaderBuilder};
+use arrow_schema::SchemaRef;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde_json::Value;
+
+use super::TableWriter;
+
+/// Rows per row group, so that a large file is not buffered whole.
+const ROW_GROUP_ROWS: usize = 10_000;
+
+/// Snappy-compressed Parquet. A Parquet file cannot be appended to, so
+/// it is created by the run that writes it, and is only readable once
+/// `finish` wrote its footer.
+pub struct ParquetWriter {
+    out: ArrowWriter<File>,
+    decoder: Decoder,
+}
+
+impl ParquetWriter {
+    pub fn open(path: &Path, schema: &SchemaRef) -> Result<Self> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_max_row_group_size(ROW_GROUP_ROWS)
+            .build();
+
+        let out = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))?;
+        let decoder = ReaderBuilder::new(schema.clone()).build_decoder()?;
+
+        Ok(Self { out, decoder })
+    }
+}
+
+impl TableWriter for ParquetWriter {
+    fn write(&mut self, rows: &[Value]) -> Result<()> {
+        self.decoder.serialize(rows)?;
+
+        if let Some(batch) = self.decoder.flush()? {
+            self.out.write(&batch)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.out.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! The tables a file sink writes and their columns. `nested` writes
+//! only `houses`, with the child rows inline; `exploded` writes the
+//! child rows to tables of their own, keyed by `external_id`.
+
+use std::sync::Arc;
+
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use serde_json::{json, Map, Value};
+
+use crate::crawler::models::HouseDetails;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Table {
+    Houses,
+    Phones,
+    Images,
+    Features,
+    PriceHistory,
+}
+
+impl Table {
+    pub fn name(self) -> &'static str {
+        match self {
+            Table::Houses => "houses",
+            Table::Phones => "phones",
+            Table::Images => "images",
+            Table::Features => "features",
+            Table::PriceHistory => "price_history",
+        }
+    }
+}
+
+/// Scalar columns of a listing, in file order.
+const HOUSE_COLUMNS: &[(&str, DataType)] = &[
+    ("external_id", DataType::Utf8),
+    ("url", DataType::Utf8),
+    ("category", DataType::Utf8),
+    ("title", DataType::Utf8),
+    ("price", DataType::Utf8),
+    ("seller_name", DataType::Utf8),
+    ("condition", DataType::Utf8),
+    ("rooms", DataType::UInt8),
+    ("house_area_m2", DataType::Float32),
+    ("land_area_m2", DataType::Float32),
+    ("construction_type", DataType::Utf8),
+    ("floors", DataType::UInt8),
+    ("bathrooms", DataType::UInt8),
+    ("garage", DataType::Utf8),
+    ("renovation", DataType::Utf8),
+    ("furniture", DataType::Utf8),
+    ("description", DataType::Utf8),
+    ("location", DataType::Utf8),
+    ("amenities", DataType::Utf8),
+    ("comfort", DataType::Utf8),
+    ("ceiling_height", DataType::Utf8),
+    ("prepayment", DataType::Utf8),
+    ("utility_payments", DataType::Utf8),
+    ("lease_type", DataType::Utf8),
+    ("minimum_rental_period", DataType::Utf8),
+    ("sewerage", DataType::Utf8),
+    ("parking", DataType::Utf8),
+    ("entrance", DataType::Utf8),
+    ("location_from_street", DataType::Utf8),
+    ("elevator", DataType::Utf8),
+    ("floor_area", DataType::Utf8),
+    ("created_at", DataType::Utf8),
+    ("updated_at", DataType::Utf8),
+    ("scraped_at", DataType::Utf8),
+];
+
+fn text(name: &str) -> Field {
+    Field::new(name, DataType::Utf8, true)
+}
+
+fn list_of(name: &str, item: DataType) -> Field {
+    Field::new(name, DataType::List(Arc::new(Field::new("item", item, true))), true)
+}
+
+fn phone_fields() -> Fields {
+    Fields::from(vec![text("raw"), text("display"), text("source")])
+}
+
+fn price_fields() -> Fields {
+    Fields::from(vec![text("date"), text("price"), text("diff")])
+}
+
+pub fn schema(table: Table, nested: bool) -> SchemaRef {
+    let fields = match table {
+        Table::Houses => {
+            let mut fields: Vec<Field> = HOUSE_COLUMNS
+                .iter()
+                .map(|(name, ty)| Field::new(*name, ty.clone(), true))
+                .collect();
+
+            if nested {
+                fields.push(list_of("phones", DataType::Struct(phone_fields())));
+                fields.push(list_of("images", DataType::Utf8));
+                fields.push(list_of("appliances", DataType::Utf8));
+                fields.push(list_of("service_lines", DataType::Utf8));
+                fields.push(list_of("facilities", DataType::Utf8));
+                fields.push(list_of("price_history", DataType::Struct(price_fields())));
+            }
+
+            fields
+        }
+        Table::Phones => vec![text("external_id"), text("raw"), text("display"), text("source")],
+        Table::Images => vec![
+            text("external_id"),
+            Field::new("position", DataType::UInt32, true),
+            text("url"),
+        ],
+        Table::Features => vec![text("external_id"), text("feature_type"), text("value")],
+        Table::PriceHistory => vec![text("external_id"), text("date"), text("price"), text("diff")],
+    };
+
+    Arc::new(Schema::new(fields))
+}
+
+/// The rows of `houses`, per table, as JSON objects named like the
+/// schema columns.
+pub fn rows(houses: &[HouseDetails], nested: bool, scraped_at: &str) -> Vec<(Table, Vec<Value>)> {
+    let mut tables: Vec<(Table, Vec<Value>)> = if nested {
+        vec![(Table::Houses, Vec::new())]
+    } else {
+        [Table::Houses, Table::Phones, Table::Images, Table::Features, Table::PriceHistory]
+            .into_iter()
+            .map(|t| (t, Vec::new()))
+            .collect()
+    };
+
+    for house in houses {
+        let full = serde_json::to_value(house).unwrap_or_default();
+
+        let mut row: Map<String, Value> = HOUSE_COLUMNS
+            .iter()
+            .map(|(name, _)| (name.to_string(), full.get(*name).cloned().unwrap_or(Value::Null)))
+            .collect();
+        row.insert("seller_name".to_string(), json!(house.contact.seller_name));
+        row.insert("scraped_at".to_string(), json!(scraped_at));
+
+        if nested {
+            row.insert("phones".to_string(), full["contact"]["phones"].clone());
+            for key in ["images", "appliances", "service_lines", "facilities", "price_history"] {
+                row.insert(key.to_string(), full[key].clone());
+            }
+            tables[0].1.push(Value::Object(row));
+            continue;
+        }
+
+        tables[0].1.push(Value::Object(row));
+
+        let id = &house.external_id;
+
+        for p in &house.contact.phones {
+            tables[1].1.push(json!({ "external_id": id, "raw": p.raw, "display": p.display, "source": p.source }));
+        }
+
+        for (position, url) in house.images.iter().enumerate() {
+            tables[2].1.push(json!({ "external_id": id, "position": position, "url": url }));
+        }
+
+        let features = [
+            ("appliances", &house.appliances),
+            ("service_lines", &house.service_lines),
+            ("facilities", &house.facilities),
+        ];
+        for (feature_type, values) in features {
+            for value in values {
+                tables[3].1.push(json!({ "external_id": id, "feature_type": feature_type, "value": value }));
+            }
+        }
+
+        for p in &house.price_history {
+            tables[4].1.push(json!({ "external_id": id, "date": p.date, "price": p.price, "diff": p.diff }));
+        }
+    }
+
+    tables
+}
@@ -0,0 +1,47 @@
+use crate::storage::postgres::PgStorage;
+
+/// Days of `list_am_daily_stats` shown by `stats`.
+const RECENT_DAYS: i64 = 7;
+
+/// Print listing, image and queue counts, the last scrape run and the
+/// recent daily aggregates.
+pub async fn print_overview(storage: &PgStorage) -> anyhow::Result<()> {
+    let o = storage.fetch_overview().await?;
+
+    println!("Listings");
+    println!("  active:          {} ({} suspected removed)", o.active, o.suspected);
+    println!("  deleted:         {}", o.deleted);
+    println!("Images");
+    println!("  downloaded:      {} of {}", o.images_downloaded, o.images);
+    println!("Detail queue");
+    println!("  pending:         {}", o.queue_pending);
+    println!("  given up:        {}", o.queue_failed);
+
+    if let Some(run) = storage.fetch_scrape_runs(1).await?.first() {
+        println!("Last scrape run");
+        println!(
+            "  {} {} at {} ({} new, {} updated)",
+            run.id,
+            run.status,
+            run.started_at.format("%Y-%m-%d %H:%M:%S"),
+            run.items_new,
+            run.items_updated
+        );
+    }
+
+    let days = storage.fetch_daily_stats(RECENT_DAYS).await?;
+    if days.is_empty() {
+        println!("\nNo daily aggregates yet (computed by the daemon's aggregates job)");
+        return Ok(());
+    }
+
+    println!("\n{:<10}  {:>7}  {:>7}  {:>11}  {:>7}", "day", "active", "created", "reactivated", "deleted");
+    for d in &days {
+        println!(
+            "{:<10}  {:>7}  {:>7}  {:>11}  {:>7}",
+            d.day, d.active_count, d.created_count, d.reactivated_count, d.deleted_count
+        );
+    }
+
+    Ok(())
+}
@@ -1 +1,187 @@
-pub mod postgres;
\ No newline at end of file
+//! Storage backends. The scrape and removal-check pipelines only need
+//! the `Storage` trait, implemented by Postgres (the production
+//! database) and SQLite (a local file or an in-memory database).
+//! Everything else (queue, scheduler, history, stats, migrations) is
+//! Postgres only.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::time::Duration;
+
+use crate::checker::schedule;
+use crate::crawler::models::HouseDetails;
+
+pub mod postgres;
+mod records;
+pub mod sqlite;
+pub mod versions;
+
+pub use records::{ActiveHouse, CheckRecord, CheckerRun, SaveStats, ScrapeCheckpoint, ScrapeRunStats};
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Listings
+
+    /// Upsert a batch of listings, recording versions and lifecycle
+    /// events and moving the phones, images and features a listing lost
+    /// to the child history; a listing is saved at most once per batch.
+    async fn save_houses_bulk(&self, houses: &[HouseDetails]) -> Result<SaveStats>;
+
+    /// Upsert one listing, returning its id.
+    async fn save_house(&self, house: &HouseDetails) -> Result<i64>;
+
+    async fn mark_houses_as_deleted(&self, ids: &[i64]) -> Result<()>;
+
+    // Removal check iteration
+
+    /// Keyset page of active houses due for a check before `due_before`,
+    /// most overdue first, strictly after the `(cursor_at, cursor_id)`
+    /// cursor.
+    async fn fetch_due_houses_batch(
+        &self,
+        limit: i64,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<Vec<ActiveHouse>>;
+
+    async fn count_due_houses(
+        &self,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<i64>;
+
+    /// One listing by external id, deleted or not, with its `deleted_at`.
+    async fn fetch_house_for_check(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<(ActiveHouse, Option<DateTime<Utc>>)>>;
+
+    async fn schedule_next_checks(&self, ids: &[i64], next_check_at: &[DateTime<Utc>]) -> Result<()>;
+
+    /// Remember a first removed verdict, keeping an existing suspicion's
+    /// timestamp.
+    async fn flag_removal_suspected(&self, ids: &[i64], reasons: &[String]) -> Result<()>;
+
+    async fn clear_removal_suspected(&self, ids: &[i64]) -> Result<()>;
+
+    async fn record_checks(&self, checks: &[CheckRecord]) -> Result<()>;
+
+    /// The saved run when the previous run of `job_name` did not finish,
+    /// otherwise a fresh one from now.
+    async fn begin_checker_run(&self, job_name: &str) -> Result<CheckerRun>;
+
+    async fn save_checker_cursor(&self, job_name: &str, cursor_at: DateTime<Utc>, cursor_id: i64) -> Result<()>;
+
+    async fn finish_checker_run(&self, job_name: &str) -> Result<()>;
+
+    // Scrape runs
+
+    async fn begin_scrape_run(&self, category: &str, start_page: i32, end_page: i32) -> Result<ScrapeCheckpoint>;
+
+    /// Latest run of `category` that did not finish.
+    async fn find_unfinished_scrape_run(&self, category: &str) -> Result<Option<ScrapeCheckpoint>>;
+
+    async fn save_scrape_checkpoint(&self, run_id: i64, page: i32, saved_external_ids: &[String]) -> Result<()>;
+
+    async fn finish_scrape_run(&self, run_id: i64) -> Result<()>;
+
+    async fn record_scrape_run_start(&self, checkpoint_id: i64, resumed: bool, config: &Value) -> Result<i64>;
+
+    async fn record_scrape_run_end(
+        &self,
+        run_id: i64,
+        status: &str,
+        stats: &ScrapeRunStats,
+        duration_ms: i64,
+    ) -> Result<()>;
+}
+
+/// Listing timestamps as scraped (RFC 3339), `None` when missing or
+/// unparseable.
+fn parse_iso(ts: &Option<String>) -> Option<DateTime<Utc>> {
+    ts.as_ref()
+        .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// When a listing saved at `now` is first probed, by the age of its
+/// last renewal.
+fn first_check_at(house: &HouseDetails, now: DateTime<Utc>) -> DateTime<Utc> {
+    let listed_at = parse_iso(&house.updated_at).or_else(|| parse_iso(&house.created_at));
+    schedule::first_check_at(listed_at, now)
+}
+
+/// Whether `database_url` points at an SQLite database.
+pub fn is_sqlite(database_url: &str) -> bool {
+    database_url.starts_with("sqlite:")
+}
+
+/// Connect to the backend `database_url` points at: `sqlite:` URLs
+/// (`sqlite:houses.db`, `sqlite::memory:`) open SQLite, anything else
+/// Postgres.
+pub async fn open(database_url: &str) -> Result<Arc<dyn Storage>> {
+    if is_sqlite(database_url) {
+        Ok(Arc::new(sqlite::SqliteStorage::open(database_url).await?))
+    } else {
+        Ok(Arc::new(postgres::PgStorage::new(database_url).await?))
+    }
+}
+
+/// Held job lock; see `lock_job`.
+pub struct JobGuard(Option<postgres::JobLock>);
+
+impl JobGuard {
+    pub async fn release(self) -> Result<()> {
+        match self.0 {
+            Some(lock) => lock.release().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run recorded in `job_runs`; see `start_job_run`.
+pub struct JobRun(Option<(postgres::PgStorage, i64)>);
+
+impl JobRun {
+    /// The `job_runs` id, `None` on SQLite.
+    pub fn id(&self) -> Option<i64> {
+        self.0.as_ref().map(|(_, id)| *id)
+    }
+
+    pub async fn finish(self, status: &str, counters: &Value, error: Option<&str>) -> Result<()> {
+        match self.0 {
+            Some((storage, id)) => storage.finish_job_run(id, status, counters, error).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Record a run of `job_name` in `job_runs` on Postgres, as the daemon
+/// does for its jobs. SQLite databases keep no run history.
+pub async fn start_job_run(database_url: &str, job_name: &str) -> Result<JobRun> {
+    if is_sqlite(database_url) {
+        return Ok(JobRun(None));
+    }
+
+    let storage = postgres::PgStorage::new(database_url).await?;
+    let id = storage.start_job_run(job_name).await?;
+    Ok(JobRun(Some((storage, id))))
+}
+
+/// Take the advisory lock of `job_name` on Postgres, `None` when another
+/// instance still holds it after `wait`. SQLite databases are local and
+/// single-user, so nothing is locked there.
+pub async fn lock_job(database_url: &str, job_name: &str, wait: Duration) -> Result<Option<JobGuard>> {
+    if is_sqlite(database_url) {
+        return Ok(Some(JobGuard(None)));
+    }
+
+    let storage = postgres::PgStorage::new(database_url).await?;
+    Ok(storage.lock_job(job_name, wait).await?.map(|lock| JobGuard(Some(lock))))
+}
@@ -6,14 +6,36 @@ use sqlx::{
     Postgres,
     Transaction,
 };
+use tokio::time::Instant;
+use tracing::info;
 
 use crate::crawler::models::HouseDetails;
+use crate::metrics;
+use crate::storage::versions::{self, HouseVersion};
+use crate::storage::{first_check_at, parse_iso, ActiveHouse, CheckRecord, CheckerRun};
 
-pub struct Storage {
+mod backend;
+mod bulk;
+mod checkpoints;
+mod export;
+mod jobs;
+mod locks;
+mod migrations;
+mod queue;
+mod runs;
+mod stats;
+
+pub use export::ExportFilter;
+pub use locks::JobLock;
+pub use migrations::MigrationState;
+pub use queue::QueuedItem;
+
+#[derive(Clone)]
+pub struct PgStorage {
     pool: PgPool,
 }
 
-impl Storage {
+impl PgStorage {
     pub async fn new(database_url: &str) -> Result<Self> {
         let pool = PgPoolOptions::new()
             .max_connections(5)
@@ -23,10 +45,20 @@ impl Storage {
         Ok(Self { pool })
     }
 
+    /// Round trip to the database, for readiness checks.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query_scalar!("SELECT 1 AS \"one!\"")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn save_houses_batch(
         &self,
         houses: &[HouseDetails],
     ) -> Result<usize> {
+        let started = Instant::now();
         let mut tx = self.pool.begin().await?;
         let mut saved = 0usize;
 
@@ -36,6 +68,7 @@ impl Storage {
         }
 
         tx.commit().await?;
+        metrics::record_upsert("row", started.elapsed());
         Ok(saved)
     }
 
@@ -54,41 +87,377 @@ impl Storage {
             return Ok(());
         }
 
-        sqlx::query!(
+        let marked = sqlx::query!(
             r#"
-            UPDATE houses_data.list_am_houses
-            SET deleted_at = now()
-            WHERE id = ANY($1)
-              AND deleted_at IS NULL
+            WITH marked AS (
+                UPDATE houses_data.list_am_houses
+                SET deleted_at = now(),
+                    removal_suspected_at = NULL
+                WHERE id = ANY($1)
+                  AND deleted_at IS NULL
+                RETURNING id, deleted_at
+            )
+            INSERT INTO houses_data.list_am_listing_events
+                (house_id, event_type, occurred_at)
+            SELECT id, 'deleted', deleted_at
+            FROM marked
             "#,
             ids
         )
             .execute(&self.pool)
             .await?;
 
+        metrics::record_deleted(marked.rows_affected() as usize);
+
         Ok(())
     }
 
-    pub async fn fetch_active_houses_batch(
+    /// Hard delete, only meant for synthetic rows (child rows cascade).
+    pub async fn delete_houses_by_prefix(&self, prefix: &str) -> Result<u64> {
+        let res = sqlx::query!(
+            r#"
+            DELETE FROM houses_data.list_am_houses
+            WHERE starts_with(external_id, $1)
+            "#,
+            prefix
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Keyset page of active houses due for a check before `due_before`,
+    /// most overdue first, strictly after the `(cursor_at, cursor_id)`
+    /// cursor. Marking rows as deleted between calls cannot shift it.
+    pub async fn fetch_due_houses_batch(
         &self,
         limit: i64,
-        offset: i64,
-    ) -> Result<Vec<(i64, String)>> {
-        let rows = sqlx::query!(
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<Vec<ActiveHouse>> {
+        let rows = sqlx::query_as!(
+            ActiveHouse,
             r#"
-            SELECT id, url
+            SELECT
+                id,
+                external_id,
+                url,
+                COALESCE(updated_at, created_at) AS listed_at,
+                next_check_at,
+                removal_suspected_at
             FROM houses_data.list_am_houses
             WHERE deleted_at IS NULL
-            ORDER BY id
-            LIMIT $1 OFFSET $2
+              AND next_check_at <= $2
+              AND (next_check_at, id) > (COALESCE($3, '-infinity'::timestamptz), $4)
+            ORDER BY next_check_at, id
+            LIMIT $1
             "#,
             limit,
-            offset
+            due_before,
+            cursor_at,
+            cursor_id
         )
             .fetch_all(&self.pool)
             .await?;
 
-        Ok(rows.into_iter().map(|r| (r.id, r.url)).collect())
+        Ok(rows)
+    }
+
+    /// One listing by external id, deleted or not, with its `deleted_at`.
+    pub async fn fetch_house_for_check(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<(ActiveHouse, Option<DateTime<Utc>>)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id,
+                external_id,
+                url,
+                COALESCE(updated_at, created_at) AS listed_at,
+                next_check_at,
+                removal_suspected_at,
+                deleted_at
+            FROM houses_data.list_am_houses
+            WHERE external_id = $1
+            "#,
+            external_id
+        )
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| {
+            let house = ActiveHouse {
+                id: r.id,
+                external_id: r.external_id,
+                url: r.url,
+                listed_at: r.listed_at,
+                next_check_at: r.next_check_at,
+                removal_suspected_at: r.removal_suspected_at,
+            };
+            (house, r.deleted_at)
+        }))
+    }
+
+    pub async fn count_due_houses(
+        &self,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM houses_data.list_am_houses
+            WHERE deleted_at IS NULL
+              AND next_check_at <= $1
+              AND (next_check_at, id) > (COALESCE($2, '-infinity'::timestamptz), $3)
+            "#,
+            due_before,
+            cursor_at,
+            cursor_id
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    pub async fn schedule_next_checks(
+        &self,
+        ids: &[i64],
+        next_check_at: &[DateTime<Utc>],
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.list_am_houses h
+            SET next_check_at = t.next_check_at,
+                last_checked_at = now()
+            FROM UNNEST($1::bigint[], $2::timestamptz[]) AS t(id, next_check_at)
+            WHERE h.id = t.id
+            "#,
+            ids,
+            next_check_at
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remember a first removed verdict. An existing suspicion keeps its
+    /// original timestamp so the confirmation window is not reset.
+    pub async fn flag_removal_suspected(
+        &self,
+        ids: &[i64],
+        reasons: &[String],
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.list_am_houses h
+            SET removal_suspected_at = COALESCE(h.removal_suspected_at, now()),
+                removal_reason = t.reason
+            FROM UNNEST($1::bigint[], $2::text[]) AS t(id, reason)
+            WHERE h.id = t.id
+            "#,
+            ids,
+            reasons
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_checks(&self, checks: &[CheckRecord]) -> Result<()> {
+        if checks.is_empty() {
+            return Ok(());
+        }
+
+        let house_ids: Vec<i64> = checks.iter().map(|c| c.house_id).collect();
+        let methods: Vec<String> = checks.iter().map(|c| c.method.to_string()).collect();
+        let statuses: Vec<Option<i16>> = checks.iter().map(|c| c.http_status).collect();
+        let final_urls: Vec<Option<String>> = checks.iter().map(|c| c.final_url.clone()).collect();
+        let latencies: Vec<i32> = checks.iter().map(|c| c.latency_ms).collect();
+        let verdicts: Vec<String> = checks.iter().map(|c| c.verdict.to_string()).collect();
+        let reasons: Vec<Option<String>> = checks.iter().map(|c| c.reason.clone()).collect();
+        let error_kinds: Vec<Option<String>> =
+            checks.iter().map(|c| c.error_kind.map(str::to_string)).collect();
+        let actions: Vec<String> = checks.iter().map(|c| c.action.to_string()).collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_checks (
+                house_id,
+                method,
+                http_status,
+                final_url,
+                latency_ms,
+                verdict,
+                reason,
+                error_kind,
+                action
+            )
+            SELECT * FROM UNNEST(
+                $1::bigint[],
+                $2::text[],
+                $3::smallint[],
+                $4::text[],
+                $5::int[],
+                $6::text[],
+                $7::text[],
+                $8::text[],
+                $9::text[]
+            )
+            "#,
+            &house_ids,
+            &methods,
+            &statuses as &[Option<i16>],
+            &final_urls as &[Option<String>],
+            &latencies,
+            &verdicts,
+            &reasons as &[Option<String>],
+            &error_kinds as &[Option<String>],
+            &actions
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_removal_suspected(&self, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.list_am_houses
+            SET removal_suspected_at = NULL,
+                removal_reason = NULL
+            WHERE id = ANY($1)
+            "#,
+            ids
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the run to continue: the saved one when the previous run
+    /// of `job_name` did not finish, otherwise a fresh one from now.
+    pub async fn begin_checker_run(&self, job_name: &str) -> Result<CheckerRun> {
+        let run = sqlx::query_as!(
+            CheckerRun,
+            r#"
+            INSERT INTO houses_data.checker_state (job_name)
+            VALUES ($1)
+            ON CONFLICT (job_name) DO UPDATE SET
+                last_id = CASE
+                    WHEN checker_state.run_finished_at IS NULL THEN checker_state.last_id
+                    ELSE 0
+                END,
+                last_next_check_at = CASE
+                    WHEN checker_state.run_finished_at IS NULL THEN checker_state.last_next_check_at
+                    ELSE NULL
+                END,
+                run_started_at = CASE
+                    WHEN checker_state.run_finished_at IS NULL THEN checker_state.run_started_at
+                    ELSE now()
+                END,
+                run_finished_at = NULL,
+                updated_at = now()
+            RETURNING
+                run_started_at AS started_at,
+                last_next_check_at AS cursor_at,
+                last_id AS cursor_id
+            "#,
+            job_name
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(run)
+    }
+
+    pub async fn save_checker_cursor(
+        &self,
+        job_name: &str,
+        cursor_at: DateTime<Utc>,
+        cursor_id: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.checker_state
+            SET last_next_check_at = $2,
+                last_id = $3,
+                updated_at = now()
+            WHERE job_name = $1
+            "#,
+            job_name,
+            cursor_at,
+            cursor_id
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_checker_run(&self, job_name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.checker_state
+            SET run_finished_at = now(),
+                updated_at = now()
+            WHERE job_name = $1
+            "#,
+            job_name
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_house_versions(
+        &self,
+        external_id: &str,
+    ) -> Result<Vec<HouseVersion>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT v.version, v.changed_fields, v.diff, v.recorded_at
+            FROM houses_data.list_am_house_versions v
+            JOIN houses_data.list_am_houses h ON h.id = v.house_id
+            WHERE h.external_id = $1
+            ORDER BY v.version
+            "#,
+            external_id
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| HouseVersion {
+                version: r.version,
+                changed_fields: r.changed_fields,
+                diff: r.diff,
+                recorded_at: r.recorded_at,
+            })
+            .collect())
     }
 
     async fn save_house_tx(
@@ -96,10 +465,24 @@ impl Storage {
         tx: &mut Transaction<'_, Postgres>,
         house: &HouseDetails,
     ) -> Result<i64> {
+        // Locked until commit: a concurrent save of the same listing waits
+        // and diffs against this one instead of racing it for the next
+        // version number
+        let previous = sqlx::query_scalar!(
+            r#"
+            SELECT to_jsonb(h) AS "row!"
+            FROM houses_data.list_am_houses h
+            WHERE external_id = $1
+            FOR UPDATE
+            "#,
+            house.external_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
 
-        let house_id = sqlx::query!(
+        let upserted = sqlx::query!(
             r#"
-            INSERT INTO houses_data.list_am_houses (
+            INSERT INTO houses_data.list_am_houses AS h (
                 external_id,
                 url,
                 title,
@@ -131,7 +514,9 @@ impl Storage {
                 elevator,
                 floor_area,
                 created_at,
-                updated_at
+                updated_at,
+                category,
+                next_check_at
             )
             VALUES (
                 $1,$2,$3,$4,
@@ -139,7 +524,9 @@ impl Storage {
                 $6,$7,$8,$9,$10,$11,$12,$13,$14,$15,
                 $16,$17,
                 $18,$19,$20,$21,$22,$23,$24,$25,$26,$27,$28,$29,$30,
-                $31,$32
+                $31,$32,
+                $33,
+                $34
             )
             ON CONFLICT (external_id) DO UPDATE SET
                 title = EXCLUDED.title,
@@ -171,8 +558,17 @@ impl Storage {
                 elevator = EXCLUDED.elevator,
                 floor_area = EXCLUDED.floor_area,
                 updated_at = EXCLUDED.updated_at,
+                category = COALESCE(EXCLUDED.category, h.category),
+                next_check_at = CASE
+                    WHEN h.deleted_at IS NOT NULL THEN EXCLUDED.next_check_at
+                    ELSE h.next_check_at
+                END,
+                relist_count = h.relist_count + (h.deleted_at IS NOT NULL)::int,
+                deleted_at = NULL,
+                removal_suspected_at = NULL,
+                removal_reason = NULL,
                 scraped_at = now()
-            RETURNING id
+            RETURNING h.id, to_jsonb(h) AS "row!"
             "#,
             house.external_id,
             house.url,
@@ -205,13 +601,53 @@ impl Storage {
             house.elevator,
             house.floor_area,
             parse_iso(&house.created_at),
-            parse_iso(&house.updated_at)
+            parse_iso(&house.updated_at),
+            house.category,
+            first_check_at(house, Utc::now())
         )
         .fetch_one(&mut **tx)
-        .await?
-        .id;        
+        .await?;
+
+        let house_id = upserted.id;
+
+        // Version history
+        Self::record_version(tx, house_id, previous.as_ref(), &upserted.row).await?;
+
+        // Lifecycle
+        match &previous {
+            None => Self::record_event(tx, house_id, "created").await?,
+            Some(prev) if !prev["deleted_at"].is_null() => {
+                info!(external_id = %house.external_id, "Removed listing reappeared, reactivating");
+                Self::record_event(tx, house_id, "reactivated").await?;
+            }
+            Some(_) => {}
+        }
 
         // Phones
+        let raws: Vec<String> = house.contact.phones.iter().map(|p| p.raw.clone()).collect();
+        let sources: Vec<String> = house.contact.phones.iter().map(|p| p.source.clone()).collect();
+
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_phones
+                WHERE house_id = $1
+                  AND (raw, source) NOT IN (
+                      SELECT * FROM UNNEST($2::text[], $3::text[])
+                  )
+                RETURNING *
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'phone', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            house_id,
+            &raws,
+            &sources
+        )
+        .execute(&mut **tx)
+        .await?;
+
         for phone in &house.contact.phones {
             sqlx::query!(
                 r#"
@@ -255,13 +691,32 @@ impl Storage {
         }
 
         // Images
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_images
+                WHERE house_id = $1
+                  AND url <> ALL($2)
+                RETURNING *
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'image', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            house_id,
+            &house.images
+        )
+        .execute(&mut **tx)
+        .await?;
+
         for (pos, url) in house.images.iter().enumerate() {
             sqlx::query!(
                 r#"
                 INSERT INTO houses_data.list_am_images
                     (house_id, position, url)
                 VALUES ($1, $2, $3)
-                ON CONFLICT DO NOTHING
+                ON CONFLICT (house_id, url)
+                DO UPDATE SET position = EXCLUDED.position
                 "#,
                 house_id,
                 pos as i32,
@@ -272,19 +727,99 @@ impl Storage {
         }
 
         // Features
-        Self::insert_features(tx, house_id, "appliances", &house.appliances).await?;
-        Self::insert_features(tx, house_id, "service_lines", &house.service_lines).await?;
-        Self::insert_features(tx, house_id, "facilities", &house.facilities).await?;
+        Self::sync_features(tx, house_id, "appliances", &house.appliances).await?;
+        Self::sync_features(tx, house_id, "service_lines", &house.service_lines).await?;
+        Self::sync_features(tx, house_id, "facilities", &house.facilities).await?;
 
         Ok(house_id)
     }
 
-    async fn insert_features(
+    /// Append a version row when tracked fields changed, or when the
+    /// house has no history yet (first insert or pre-existing row).
+    async fn record_version(
+        tx: &mut Transaction<'_, Postgres>,
+        house_id: i64,
+        previous: Option<&serde_json::Value>,
+        current: &serde_json::Value,
+    ) -> Result<()> {
+        let snapshot = versions::tracked_snapshot(current);
+
+        let (changed_fields, diff) = match previous {
+            Some(prev) => versions::diff_snapshots(&versions::tracked_snapshot(prev), &snapshot),
+            None => (vec![], serde_json::json!({})),
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_house_versions
+                (house_id, version, changed_fields, diff, snapshot)
+            SELECT
+                $1,
+                COALESCE(MAX(version), 0) + 1,
+                $2,
+                $3,
+                $4
+            FROM houses_data.list_am_house_versions
+            WHERE house_id = $1
+            HAVING cardinality($2::text[]) > 0 OR COUNT(*) = 0
+            "#,
+            house_id,
+            &changed_fields,
+            diff,
+            snapshot
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_event(
+        tx: &mut Transaction<'_, Postgres>,
+        house_id: i64,
+        event_type: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_listing_events
+                (house_id, event_type)
+            VALUES ($1, $2)
+            "#,
+            house_id,
+            event_type
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn sync_features(
         tx: &mut Transaction<'_, Postgres>,
         house_id: i64,
         feature_type: &str,
         values: &[String],
     ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_features
+                WHERE house_id = $1
+                  AND feature_type = $2
+                  AND value <> ALL($3)
+                RETURNING *
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'feature', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            house_id,
+            feature_type,
+            values
+        )
+        .execute(&mut **tx)
+        .await?;
+
         for v in values {
             sqlx::query!(
                 r#"
@@ -304,9 +839,3 @@ impl Storage {
         Ok(())
     }
 }
-
-fn parse_iso(ts: &Option<String>) -> Option<DateTime<Utc>> {
-    ts.as_ref()
-        .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
-        .map(|dt| dt.with_timezone(&Utc))
-}
\ No newline at end of file
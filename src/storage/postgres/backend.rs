@@ -0,0 +1,111 @@
+//! `Storage` over the inherent Postgres methods.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::PgStorage;
+use crate::crawler::models::HouseDetails;
+use crate::storage::{
+    ActiveHouse, CheckRecord, CheckerRun, SaveStats, ScrapeCheckpoint, ScrapeRunStats, Storage,
+};
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn save_houses_bulk(&self, houses: &[HouseDetails]) -> Result<SaveStats> {
+        PgStorage::save_houses_bulk(self, houses).await
+    }
+
+    async fn save_house(&self, house: &HouseDetails) -> Result<i64> {
+        PgStorage::save_house(self, house).await
+    }
+
+    async fn mark_houses_as_deleted(&self, ids: &[i64]) -> Result<()> {
+        PgStorage::mark_houses_as_deleted(self, ids).await
+    }
+
+    async fn fetch_due_houses_batch(
+        &self,
+        limit: i64,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<Vec<ActiveHouse>> {
+        PgStorage::fetch_due_houses_batch(self, limit, due_before, cursor_at, cursor_id).await
+    }
+
+    async fn count_due_houses(
+        &self,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<i64> {
+        PgStorage::count_due_houses(self, due_before, cursor_at, cursor_id).await
+    }
+
+    async fn fetch_house_for_check(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<(ActiveHouse, Option<DateTime<Utc>>)>> {
+        PgStorage::fetch_house_for_check(self, external_id).await
+    }
+
+    async fn schedule_next_checks(&self, ids: &[i64], next_check_at: &[DateTime<Utc>]) -> Result<()> {
+        PgStorage::schedule_next_checks(self, ids, next_check_at).await
+    }
+
+    async fn flag_removal_suspected(&self, ids: &[i64], reasons: &[String]) -> Result<()> {
+        PgStorage::flag_removal_suspected(self, ids, reasons).await
+    }
+
+    async fn clear_removal_suspected(&self, ids: &[i64]) -> Result<()> {
+        PgStorage::clear_removal_suspected(self, ids).await
+    }
+
+    async fn record_checks(&self, checks: &[CheckRecord]) -> Result<()> {
+        PgStorage::record_checks(self, checks).await
+    }
+
+    async fn begin_checker_run(&self, job_name: &str) -> Result<CheckerRun> {
+        PgStorage::begin_checker_run(self, job_name).await
+    }
+
+    async fn save_checker_cursor(&self, job_name: &str, cursor_at: DateTime<Utc>, cursor_id: i64) -> Result<()> {
+        PgStorage::save_checker_cursor(self, job_name, cursor_at, cursor_id).await
+    }
+
+    async fn finish_checker_run(&self, job_name: &str) -> Result<()> {
+        PgStorage::finish_checker_run(self, job_name).await
+    }
+
+    async fn begin_scrape_run(&self, category: &str, start_page: i32, end_page: i32) -> Result<ScrapeCheckpoint> {
+        PgStorage::begin_scrape_run(self, category, start_page, end_page).await
+    }
+
+    async fn find_unfinished_scrape_run(&self, category: &str) -> Result<Option<ScrapeCheckpoint>> {
+        PgStorage::find_unfinished_scrape_run(self, category).await
+    }
+
+    async fn save_scrape_checkpoint(&self, run_id: i64, page: i32, saved_external_ids: &[String]) -> Result<()> {
+        PgStorage::save_scrape_checkpoint(self, run_id, page, saved_external_ids).await
+    }
+
+    async fn finish_scrape_run(&self, run_id: i64) -> Result<()> {
+        PgStorage::finish_scrape_run(self, run_id).await
+    }
+
+    async fn record_scrape_run_start(&self, checkpoint_id: i64, resumed: bool, config: &Value) -> Result<i64> {
+        PgStorage::record_scrape_run_start(self, checkpoint_id, resumed, config).await
+    }
+
+    async fn record_scrape_run_end(
+        &self,
+        run_id: i64,
+        status: &str,
+        stats: &ScrapeRunStats,
+        duration_ms: i64,
+    ) -> Result<()> {
+        PgStorage::record_scrape_run_end(self, run_id, status, stats, duration_ms).await
+    }
+}
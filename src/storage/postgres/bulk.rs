@@ -0,0 +1,570 @@
+//! Set-based upsert path: every table is written with one statement per
+//! batch (`UNNEST` over parallel arrays) instead of one per row.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{Postgres, Transaction};
+use tokio::time::Instant;
+use tracing::info;
+
+use super::{first_check_at, parse_iso, PgStorage};
+use crate::crawler::models::HouseDetails;
+use crate::metrics;
+use crate::storage::versions;
+use crate::storage::SaveStats;
+
+impl PgStorage {
+    /// Same semantics as `save_houses_batch` (versions, lifecycle events,
+    /// child reconciliation) with a fixed number of round trips per batch.
+    pub async fn save_houses_bulk(
+        &self,
+        houses: &[HouseDetails],
+    ) -> Result<SaveStats> {
+        let mut stats = SaveStats::default();
+
+        if houses.is_empty() {
+            return Ok(stats);
+        }
+
+        // ON CONFLICT DO UPDATE cannot touch the same row twice in one
+        // statement, so keep only the last occurrence of each listing.
+        let mut by_external_id: HashMap<&str, &HouseDetails> = HashMap::new();
+        for house in houses {
+            by_external_id.insert(house.external_id.as_str(), house);
+        }
+        let houses: Vec<&HouseDetails> = by_external_id.into_values().collect();
+
+        let started = Instant::now();
+        let mut tx = self.pool.begin().await?;
+
+        let external_ids: Vec<String> = houses.iter().map(|h| h.external_id.clone()).collect();
+
+        // Locked in id order until commit, so concurrent batches sharing
+        // listings take turns instead of racing for version numbers
+        let previous: HashMap<String, Value> = sqlx::query!(
+            r#"
+            SELECT external_id, to_jsonb(h) AS "row!"
+            FROM houses_data.list_am_houses h
+            WHERE external_id = ANY($1)
+            ORDER BY id
+            FOR UPDATE
+            "#,
+            &external_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|r| (r.external_id, r.row))
+        .collect();
+
+        let upserted = Self::upsert_houses_bulk(&mut tx, &houses).await?;
+
+        let house_ids: HashMap<String, i64> = upserted
+            .iter()
+            .map(|(id, external_id, _)| (external_id.clone(), *id))
+            .collect();
+
+        // Version history + lifecycle
+        let mut version_rows = Vec::with_capacity(upserted.len());
+        let mut event_house_ids = Vec::new();
+        let mut event_types = Vec::new();
+
+        for (house_id, external_id, row) in &upserted {
+            let snapshot = versions::tracked_snapshot(row);
+            let prev = previous.get(external_id);
+
+            let (changed, diff) = match prev {
+                Some(p) => versions::diff_snapshots(&versions::tracked_snapshot(p), &snapshot),
+                None => (vec![], serde_json::json!({})),
+            };
+
+            match prev {
+                None => {
+                    stats.new += 1;
+                    event_house_ids.push(*house_id);
+                    event_types.push("created".to_string());
+                }
+                Some(p) if !p["deleted_at"].is_null() => {
+                    info!(external_id = %external_id, "Removed listing reappeared, reactivating");
+                    stats.updated += 1;
+                    event_house_ids.push(*house_id);
+                    event_types.push("reactivated".to_string());
+                }
+                Some(_) if !changed.is_empty() => stats.updated += 1,
+                Some(_) => stats.unchanged += 1,
+            }
+
+            version_rows.push((*house_id, Value::from(changed), diff, snapshot));
+        }
+
+        Self::insert_versions_bulk(&mut tx, version_rows).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_listing_events (house_id, event_type)
+            SELECT * FROM UNNEST($1::bigint[], $2::text[])
+            "#,
+            &event_house_ids,
+            &event_types
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let all_ids: Vec<i64> = house_ids.values().copied().collect();
+        let id_of = |h: &HouseDetails| house_ids[&h.external_id];
+
+        Self::sync_phones_bulk(&mut tx, &all_ids, &houses, id_of).await?;
+        Self::insert_price_history_bulk(&mut tx, &houses, id_of).await?;
+        Self::sync_images_bulk(&mut tx, &all_ids, &houses, id_of).await?;
+        Self::sync_features_bulk(&mut tx, &all_ids, &houses, id_of).await?;
+
+        tx.commit().await?;
+
+        metrics::record_upsert("bulk", started.elapsed());
+        metrics::record_saved("new", stats.new);
+        metrics::record_saved("updated", stats.updated);
+        metrics::record_saved("unchanged", stats.unchanged);
+
+        Ok(stats)
+    }
+
+    async fn upsert_houses_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        houses: &[&HouseDetails],
+    ) -> Result<Vec<(i64, String, Value)>> {
+        let col = |f: fn(&HouseDetails) -> Option<String>| -> Vec<Option<String>> {
+            houses.iter().map(|h| f(h)).collect()
+        };
+        let small = |f: fn(&HouseDetails) -> Option<u8>| -> Vec<Option<i16>> {
+            houses.iter().map(|h| f(h).map(|v| v as i16)).collect()
+        };
+        let area = |f: fn(&HouseDetails) -> Option<f32>| -> Vec<Option<f32>> {
+            houses.iter().map(|h| f(h)).collect()
+        };
+        let ts = |f: fn(&HouseDetails) -> &Option<String>| -> Vec<Option<DateTime<Utc>>> {
+            houses.iter().map(|h| parse_iso(f(h))).collect()
+        };
+        let now = Utc::now();
+
+        let rows = sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_houses AS h (
+                external_id,
+                url,
+                title,
+                price,
+                seller_name,
+                condition,
+                rooms,
+                house_area_m2,
+                land_area_m2,
+                construction_type,
+                floors,
+                bathrooms,
+                garage,
+                renovation,
+                furniture,
+                description,
+                location,
+                amenities,
+                comfort,
+                ceiling_height,
+                prepayment,
+                utility_payments,
+                lease_type,
+                minimum_rental_period,
+                sewerage,
+                parking,
+                entrance,
+                location_from_street,
+                elevator,
+                floor_area,
+                created_at,
+                updated_at,
+                category,
+                next_check_at
+            )
+            SELECT * FROM UNNEST(
+                $1::text[], $2::text[], $3::text[], $4::text[],
+                $5::text[],
+                $6::text[], $7::smallint[], $8::real[], $9::real[], $10::text[],
+                $11::smallint[], $12::smallint[], $13::text[], $14::text[], $15::text[],
+                $16::text[], $17::text[],
+                $18::text[], $19::text[], $20::text[], $21::text[], $22::text[],
+                $23::text[], $24::text[], $25::text[], $26::text[], $27::text[],
+                $28::text[], $29::text[], $30::text[],
+                $31::timestamptz[], $32::timestamptz[],
+                $33::text[],
+                $34::timestamptz[]
+            )
+            ON CONFLICT (external_id) DO UPDATE SET
+                title = EXCLUDED.title,
+                price = EXCLUDED.price,
+                seller_name = EXCLUDED.seller_name,
+                condition = EXCLUDED.condition,
+                rooms = EXCLUDED.rooms,
+                house_area_m2 = EXCLUDED.house_area_m2,
+                land_area_m2 = EXCLUDED.land_area_m2,
+                construction_type = EXCLUDED.construction_type,
+                floors = EXCLUDED.floors,
+                bathrooms = EXCLUDED.bathrooms,
+                garage = EXCLUDED.garage,
+                renovation = EXCLUDED.renovation,
+                furniture = EXCLUDED.furniture,
+                description = EXCLUDED.description,
+                location = EXCLUDED.location,
+                amenities = EXCLUDED.amenities,
+                comfort = EXCLUDED.comfort,
+                ceiling_height = EXCLUDED.ceiling_height,
+                prepayment = EXCLUDED.prepayment,
+                utility_payments = EXCLUDED.utility_payments,
+                lease_type = EXCLUDED.lease_type,
+                minimum_rental_period = EXCLUDED.minimum_rental_period,
+                sewerage = EXCLUDED.sewerage,
+                parking = EXCLUDED.parking,
+                entrance = EXCLUDED.entrance,
+                location_from_street = EXCLUDED.location_from_street,
+                elevator = EXCLUDED.elevator,
+                floor_area = EXCLUDED.floor_area,
+                updated_at = EXCLUDED.updated_at,
+                category = COALESCE(EXCLUDED.category, h.category),
+                next_check_at = CASE
+                    WHEN h.deleted_at IS NOT NULL THEN EXCLUDED.next_check_at
+                    ELSE h.next_check_at
+                END,
+                relist_count = h.relist_count + (h.deleted_at IS NOT NULL)::int,
+                deleted_at = NULL,
+                removal_suspected_at = NULL,
+                removal_reason = NULL,
+                scraped_at = now()
+            RETURNING h.id, h.external_id, to_jsonb(h) AS "row!"
+            "#,
+            &houses.iter().map(|h| h.external_id.clone()).collect::<Vec<_>>(),
+            &houses.iter().map(|h| h.url.clone()).collect::<Vec<_>>(),
+            &col(|h| h.title.clone()) as &[Option<String>],
+            &col(|h| h.price.clone()) as &[Option<String>],
+            &col(|h| h.contact.seller_name.clone()) as &[Option<String>],
+            &col(|h| h.condition.clone()) as &[Option<String>],
+            &small(|h| h.rooms) as &[Option<i16>],
+            &area(|h| h.house_area_m2) as &[Option<f32>],
+            &area(|h| h.land_area_m2) as &[Option<f32>],
+            &col(|h| h.construction_type.clone()) as &[Option<String>],
+            &small(|h| h.floors) as &[Option<i16>],
+            &small(|h| h.bathrooms) as &[Option<i16>],
+            &col(|h| h.garage.clone()) as &[Option<String>],
+            &col(|h| h.renovation.clone()) as &[Option<String>],
+            &col(|h| h.furniture.clone()) as &[Option<String>],
+            &houses.iter().map(|h| h.description.clone()).collect::<Vec<_>>(),
+            &col(|h| h.location.clone()) as &[Option<String>],
+            &col(|h| h.amenities.clone()) as &[Option<String>],
+            &col(|h| h.comfort.clone()) as &[Option<String>],
+            &col(|h| h.ceiling_height.clone()) as &[Option<String>],
+            &col(|h| h.prepayment.clone()) as &[Option<String>],
+            &col(|h| h.utility_payments.clone()) as &[Option<String>],
+            &col(|h| h.lease_type.clone()) as &[Option<String>],
+            &col(|h| h.minimum_rental_period.clone()) as &[Option<String>],
+            &col(|h| h.sewerage.clone()) as &[Option<String>],
+            &col(|h| h.parking.clone()) as &[Option<String>],
+            &col(|h| h.entrance.clone()) as &[Option<String>],
+            &col(|h| h.location_from_street.clone()) as &[Option<String>],
+            &col(|h| h.elevator.clone()) as &[Option<String>],
+            &col(|h| h.floor_area.clone()) as &[Option<String>],
+            &ts(|h| &h.created_at) as &[Option<DateTime<Utc>>],
+            &ts(|h| &h.updated_at) as &[Option<DateTime<Utc>>],
+            &col(|h| h.category.clone()) as &[Option<String>],
+            &houses.iter().map(|h| first_check_at(h, now)).collect::<Vec<_>>()
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.id, r.external_id, r.row)).collect())
+    }
+
+    async fn insert_versions_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        rows: Vec<(i64, Value, Value, Value)>,
+    ) -> Result<()> {
+        let mut house_ids = Vec::with_capacity(rows.len());
+        let mut changed = Vec::with_capacity(rows.len());
+        let mut diffs = Vec::with_capacity(rows.len());
+        let mut snapshots = Vec::with_capacity(rows.len());
+
+        for (id, c, d, s) in rows {
+            house_ids.push(id);
+            changed.push(c);
+            diffs.push(d);
+            snapshots.push(s);
+        }
+
+        // Same rule as record_version: on change, or when no history exists
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_house_versions
+                (house_id, version, changed_fields, diff, snapshot)
+            SELECT
+                t.house_id,
+                COALESCE(v.last_version, 0) + 1,
+                ARRAY(SELECT jsonb_array_elements_text(t.changed)),
+                t.diff,
+                t.snapshot
+            FROM UNNEST($1::bigint[], $2::jsonb[], $3::jsonb[], $4::jsonb[])
+                AS t(house_id, changed, diff, snapshot)
+            LEFT JOIN (
+                SELECT house_id, MAX(version) AS last_version
+                FROM houses_data.list_am_house_versions
+                WHERE house_id = ANY($1)
+                GROUP BY house_id
+            ) v USING (house_id)
+            WHERE jsonb_array_length(t.changed) > 0
+               OR v.last_version IS NULL
+            "#,
+            &house_ids,
+            &changed,
+            &diffs,
+            &snapshots
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn sync_phones_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        all_ids: &[i64],
+        houses: &[&HouseDetails],
+        id_of: impl Fn(&HouseDetails) -> i64,
+    ) -> Result<()> {
+        // One phone per source per house: the last one wins, as in the row path
+        let mut phones: HashMap<(i64, String), (String, String)> = HashMap::new();
+        for house in houses {
+            for p in &house.contact.phones {
+                phones.insert((id_of(house), p.source.clone()), (p.raw.clone(), p.display.clone()));
+            }
+        }
+
+        let mut house_ids = Vec::with_capacity(phones.len());
+        let mut sources = Vec::with_capacity(phones.len());
+        let mut raws = Vec::with_capacity(phones.len());
+        let mut displays = Vec::with_capacity(phones.len());
+
+        for ((id, source), (raw, display)) in phones {
+            house_ids.push(id);
+            sources.push(source);
+            raws.push(raw);
+            displays.push(display);
+        }
+
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_phones p
+                WHERE p.house_id = ANY($1)
+                  AND (p.house_id, p.raw, p.source) NOT IN (
+                      SELECT * FROM UNNEST($2::bigint[], $3::text[], $4::text[])
+                  )
+                RETURNING p.*
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'phone', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            all_ids,
+            &house_ids,
+            &raws,
+            &sources
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_phones
+                (house_id, raw, display, source)
+            SELECT * FROM UNNEST($1::bigint[], $2::text[], $3::text[], $4::text[])
+            ON CONFLICT (house_id, source)
+            DO UPDATE SET
+                raw = EXCLUDED.raw,
+                display = EXCLUDED.display
+            "#,
+            &house_ids,
+            &raws,
+            &displays,
+            &sources
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_price_history_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        houses: &[&HouseDetails],
+        id_of: impl Fn(&HouseDetails) -> i64,
+    ) -> Result<()> {
+        let mut house_ids = Vec::new();
+        let mut dates = Vec::new();
+        let mut prices = Vec::new();
+        let mut diffs = Vec::new();
+
+        for house in houses {
+            for p in &house.price_history {
+                house_ids.push(id_of(house));
+                dates.push(
+                    DateTime::parse_from_rfc3339(&p.date)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .ok(),
+                );
+                prices.push(p.price.clone());
+                diffs.push(p.diff.clone());
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_price_history
+                (house_id, date, price, diff)
+            SELECT * FROM UNNEST($1::bigint[], $2::timestamptz[], $3::text[], $4::text[])
+            ON CONFLICT (house_id, date, price, diff) DO NOTHING
+            "#,
+            &house_ids,
+            &dates as &[Option<DateTime<Utc>>],
+            &prices,
+            &diffs as &[Option<String>]
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn sync_images_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        all_ids: &[i64],
+        houses: &[&HouseDetails],
+        id_of: impl Fn(&HouseDetails) -> i64,
+    ) -> Result<()> {
+        // Repeated urls keep their last position, as in the row path
+        let mut images: HashMap<(i64, String), i32> = HashMap::new();
+        for house in houses {
+            for (pos, url) in house.images.iter().enumerate() {
+                images.insert((id_of(house), url.clone()), pos as i32);
+            }
+        }
+
+        let mut house_ids = Vec::with_capacity(images.len());
+        let mut urls = Vec::with_capacity(images.len());
+        let mut positions = Vec::with_capacity(images.len());
+
+        for ((id, url), pos) in images {
+            house_ids.push(id);
+            urls.push(url);
+            positions.push(pos);
+        }
+
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_images i
+                WHERE i.house_id = ANY($1)
+                  AND (i.house_id, i.url) NOT IN (
+                      SELECT * FROM UNNEST($2::bigint[], $3::text[])
+                  )
+                RETURNING i.*
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'image', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            all_ids,
+            &house_ids,
+            &urls
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_images
+                (house_id, position, url)
+            SELECT * FROM UNNEST($1::bigint[], $2::int[], $3::text[])
+            ON CONFLICT (house_id, url)
+            DO UPDATE SET position = EXCLUDED.position
+            "#,
+            &house_ids,
+            &positions,
+            &urls
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn sync_features_bulk(
+        tx: &mut Transaction<'_, Postgres>,
+        all_ids: &[i64],
+        houses: &[&HouseDetails],
+        id_of: impl Fn(&HouseDetails) -> i64,
+    ) -> Result<()> {
+        let mut house_ids = Vec::new();
+        let mut types = Vec::new();
+        let mut values = Vec::new();
+
+        for house in houses {
+            for (feature_type, list) in [
+                ("appliances", &house.appliances),
+                ("service_lines", &house.service_lines),
+                ("facilities", &house.facilities),
+            ] {
+                for v in list {
+                    house_ids.push(id_of(house));
+                    types.push(feature_type.to_string());
+                    values.push(v.clone());
+                }
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            WITH removed AS (
+                DELETE FROM houses_data.list_am_features f
+                WHERE f.house_id = ANY($1)
+                  AND (f.house_id, f.feature_type, f.value) NOT IN (
+                      SELECT * FROM UNNEST($2::bigint[], $3::text[], $4::text[])
+                  )
+                RETURNING f.*
+            )
+            INSERT INTO houses_data.list_am_child_history (house_id, kind, data)
+            SELECT house_id, 'feature', to_jsonb(r) - 'id' - 'house_id'
+            FROM removed r
+            "#,
+            all_ids,
+            &house_ids,
+            &types,
+            &values
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_features
+                (house_id, feature_type, value)
+            SELECT * FROM UNNEST($1::bigint[], $2::text[], $3::text[])
+            ON CONFLICT (house_id, feature_type, value) DO NOTHING
+            "#,
+            &house_ids,
+            &types,
+            &values
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
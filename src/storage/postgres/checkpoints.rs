@@ -0,0 +1,94 @@
+//! Scrape run checkpoints (`scrape_checkpoints`).
+
+use anyhow::Result;
+
+use super::PgStorage;
+use crate::storage::ScrapeCheckpoint;
+
+impl PgStorage {
+    pub async fn begin_scrape_run(
+        &self,
+        category: &str,
+        start_page: i32,
+        end_page: i32,
+    ) -> Result<ScrapeCheckpoint> {
+        let checkpoint = sqlx::query_as!(
+            ScrapeCheckpoint,
+            r#"
+            INSERT INTO houses_data.scrape_checkpoints (category, start_page, end_page, page)
+            VALUES ($1, $2, $3, $2)
+            RETURNING id, start_page, end_page, page, saved_external_ids
+            "#,
+            category,
+            start_page,
+            end_page
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(checkpoint)
+    }
+
+    /// Latest run of `category` that did not finish.
+    pub async fn find_unfinished_scrape_run(
+        &self,
+        category: &str,
+    ) -> Result<Option<ScrapeCheckpoint>> {
+        let checkpoint = sqlx::query_as!(
+            ScrapeCheckpoint,
+            r#"
+            SELECT id, start_page, end_page, page, saved_external_ids
+            FROM houses_data.scrape_checkpoints
+            WHERE category = $1
+              AND finished_at IS NULL
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+            category
+        )
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(checkpoint)
+    }
+
+    pub async fn save_scrape_checkpoint(
+        &self,
+        run_id: i64,
+        page: i32,
+        saved_external_ids: &[String],
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.scrape_checkpoints
+            SET page = $2,
+                saved_external_ids = $3,
+                updated_at = now()
+            WHERE id = $1
+            "#,
+            run_id,
+            page,
+            saved_external_ids
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn finish_scrape_run(&self, run_id: i64) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.scrape_checkpoints
+            SET finished_at = now(),
+                updated_at = now()
+            WHERE id = $1
+            "#,
+            run_id
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
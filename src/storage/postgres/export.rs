@@ -0,0 +1,139 @@
+//! Denormalized listings behind the `export` command.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+use super::PgStorage;
+
+/// Which listings to export; `None` and `false` do not filter.
+#[derive(Debug, Default)]
+pub struct ExportFilter {
+    /// Listing base URL, as saved in `category`.
+    pub category: Option<String>,
+    /// First and last day (UTC) of `created_at`, inclusive.
+    pub created_from: Option<NaiveDate>,
+    pub created_to: Option<NaiveDate>,
+    pub active_only: bool,
+    /// Currency sign `price` must contain (`$`, `֏`, ...).
+    pub currency: Option<String>,
+    /// Bounds on the first number of `price`; only meaningful together
+    /// with `currency`.
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    /// Case-insensitive substring of `location`.
+    pub district: Option<String>,
+}
+
+impl PgStorage {
+    /// The listings matching `filter` in id order, each a JSON object of
+    /// its columns with its phones, images, features and price history
+    /// inline. Rows are streamed as Postgres sends them.
+    pub fn export_houses<'a>(&'a self, filter: &'a ExportFilter) -> BoxStream<'a, Result<Value>> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT to_jsonb(h) || jsonb_build_object(
+                'phones', COALESCE((
+                    SELECT jsonb_agg(
+                        jsonb_build_object('raw', p.raw, 'display', p.display, 'source', p.source)
+                        ORDER BY p.id
+                    )
+                    FROM houses_data.list_am_phones p
+                    WHERE p.house_id = h.id
+                ), '[]'),
+                'images', COALESCE((
+                    SELECT jsonb_agg(i.url ORDER BY i.position, i.id)
+                    FROM houses_data.list_am_images i
+                    WHERE i.house_id = h.id
+                ), '[]'),
+                'appliances', COALESCE((
+                    SELECT jsonb_agg(f.value ORDER BY f.id)
+                    FROM houses_data.list_am_features f
+                    WHERE f.house_id = h.id AND f.feature_type = 'appliances'
+                ), '[]'),
+                'service_lines', COALESCE((
+                    SELECT jsonb_agg(f.value ORDER BY f.id)
+                    FROM houses_data.list_am_features f
+                    WHERE f.house_id = h.id AND f.feature_type = 'service_lines'
+                ), '[]'),
+                'facilities', COALESCE((
+                    SELECT jsonb_agg(f.value ORDER BY f.id)
+                    FROM houses_data.list_am_features f
+                    WHERE f.house_id = h.id AND f.feature_type = 'facilities'
+                ), '[]'),
+                'price_history', COALESCE((
+                    SELECT jsonb_agg(
+                        jsonb_build_object('date', ph.date, 'price', ph.price, 'diff', ph.diff)
+                        ORDER BY ph.date, ph.id
+                    )
+                    FROM houses_data.list_am_price_history ph
+                    WHERE ph.house_id = h.id
+                ), '[]')
+            ) AS "row!"
+            FROM houses_data.list_am_houses h
+            WHERE ($1::text IS NULL OR h.category = $1)
+              AND ($2::date IS NULL OR h.created_at >= $2::date::timestamp AT TIME ZONE 'UTC')
+              AND ($3::date IS NULL OR h.created_at < ($3::date + 1)::timestamp AT TIME ZONE 'UTC')
+              AND (NOT $4 OR h.deleted_at IS NULL)
+              AND ($5::bigint IS NULL
+                   OR replace(substring(h.price FROM '[0-9][0-9,]*'), ',', '')::numeric >= $5)
+              AND ($6::bigint IS NULL
+                   OR replace(substring(h.price FROM '[0-9][0-9,]*'), ',', '')::numeric <= $6)
+              AND ($7::text IS NULL OR h.location ILIKE $7)
+              AND ($8::text IS NULL OR strpos(h.price, $8) > 0)
+            ORDER BY h.id
+            "#,
+            filter.category,
+            filter.created_from,
+            filter.created_to,
+            filter.active_only,
+            filter.min_price,
+            filter.max_price,
+            filter.district.as_deref().map(|d| format!("%{}%", escape_like(d))),
+            filter.currency
+        )
+            .fetch(&self.pool)
+            .map_err(anyhow::Error::from)
+            .boxed()
+    }
+
+    /// Listings saved before their category was recorded, which a
+    /// category filter leaves out.
+    pub async fn count_uncategorized_houses(&self) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM houses_data.list_am_houses WHERE category IS NULL"#
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+}
+
+/// `s` matched literally by `LIKE`: its wildcards and the escape
+/// character are escaped.
+fn escape_like(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_like;
+
+    #[test]
+    fn like_wildcards_are_escaped() {
+        assert_eq!(escape_like("Arabkir"), "Arabkir");
+        assert_eq!(escape_like("50%_off"), "50\\%\\_off");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+    }
+}
@@ -0,0 +1,178 @@
+//! Bookkeeping for the `daemon` jobs: run history, pending image
+//! downloads and the daily aggregates.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::PgStorage;
+
+/// An image row whose file has not been downloaded yet.
+#[derive(Debug)]
+pub struct PendingImage {
+    pub id: i64,
+    pub external_id: String,
+    pub position: i32,
+    pub url: String,
+}
+
+impl PgStorage {
+    pub async fn start_job_run(&self, job_name: &str) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO houses_data.job_runs (job_name)
+            VALUES ($1)
+            RETURNING id
+            "#,
+            job_name
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    pub async fn finish_job_run(
+        &self,
+        run_id: i64,
+        status: &str,
+        counters: &Value,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.job_runs
+            SET finished_at = now(),
+                status = $2,
+                counters = $3,
+                error = $4
+            WHERE id = $1
+            "#,
+            run_id,
+            status,
+            counters,
+            error
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// `(status, finished_at)` of the last run of `job_name` that either
+    /// succeeded or failed; skipped and interrupted runs say nothing
+    /// about the job's health.
+    pub async fn last_job_outcome(&self, job_name: &str) -> Result<Option<(String, DateTime<Utc>)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT status, finished_at AS "finished_at!"
+            FROM houses_data.job_runs
+            WHERE job_name = $1
+              AND status IN ('succeeded', 'failed')
+              AND finished_at IS NOT NULL
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+            job_name
+        )
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| (r.status, r.finished_at)))
+    }
+
+    /// Images of active listings never downloaded, keyset-paginated on id.
+    pub async fn fetch_pending_images(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<PendingImage>> {
+        let rows = sqlx::query_as!(
+            PendingImage,
+            r#"
+            SELECT i.id, h.external_id, i.position, i.url
+            FROM houses_data.list_am_images i
+            JOIN houses_data.list_am_houses h ON h.id = i.house_id
+            WHERE i.downloaded_at IS NULL
+              AND h.deleted_at IS NULL
+              AND i.id > $1
+            ORDER BY i.id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn mark_images_downloaded(&self, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.list_am_images
+            SET downloaded_at = now()
+            WHERE id = ANY($1)
+            "#,
+            ids
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Recompute `list_am_daily_stats` for today and the `days` before it.
+    /// Returns the number of days written.
+    pub async fn refresh_daily_stats(&self, days: i32) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO houses_data.list_am_daily_stats (
+                day,
+                active_count,
+                created_count,
+                reactivated_count,
+                deleted_count,
+                computed_at
+            )
+            SELECT
+                d.day::date,
+                (
+                    SELECT COUNT(*)
+                    FROM houses_data.list_am_listing_periods p
+                    WHERE p.started_at < d.day + interval '1 day'
+                      AND (p.ended_at IS NULL OR p.ended_at >= d.day + interval '1 day')
+                ),
+                COUNT(e.id) FILTER (WHERE e.event_type = 'created'),
+                COUNT(e.id) FILTER (WHERE e.event_type = 'reactivated'),
+                COUNT(e.id) FILTER (WHERE e.event_type = 'deleted'),
+                now()
+            FROM generate_series(
+                (current_date - $1::int)::timestamptz,
+                current_date::timestamptz,
+                interval '1 day'
+            ) AS d(day)
+            LEFT JOIN houses_data.list_am_listing_events e
+                ON e.occurred_at >= d.day
+               AND e.occurred_at < d.day + interval '1 day'
+            GROUP BY d.day
+            ON CONFLICT (day) DO UPDATE SET
+                active_count = EXCLUDED.active_count,
+                created_count = EXCLUDED.created_count,
+                reactivated_count = EXCLUDED.reactivated_count,
+                deleted_count = EXCLUDED.deleted_count,
+                computed_at = EXCLUDED.computed_at
+            "#,
+            days
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
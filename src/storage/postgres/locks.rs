@@ -0,0 +1,75 @@
+//! Cross-process job exclusion with Postgres session advisory locks.
+//!
+//! The lock lives on a connection taken out of the pool, so it is
+//! released when the job finishes, and by Postgres itself when the
+//! process dies and the connection drops.
+
+use anyhow::Result;
+use sqlx::{Connection, PgConnection};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{info, warn};
+
+use super::PgStorage;
+
+/// How often a waiting instance retries a held lock.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Held advisory lock of one job; dropping it closes the connection,
+/// which releases the lock as well.
+pub struct JobLock {
+    conn: PgConnection,
+    job_name: String,
+}
+
+impl PgStorage {
+    /// Take the lock of `job_name`, waiting up to `wait` for another
+    /// instance to release it. `None` when it is still held after that.
+    pub async fn lock_job(&self, job_name: &str, wait: Duration) -> Result<Option<JobLock>> {
+        let mut conn = self.pool.acquire().await?.detach();
+        let deadline = Instant::now() + wait;
+        let mut logged = false;
+
+        loop {
+            let acquired = sqlx::query_scalar!(
+                r#"SELECT pg_try_advisory_lock(hashtextextended($1, 0)) AS "acquired!""#,
+                job_name
+            )
+                .fetch_one(&mut conn)
+                .await?;
+
+            if acquired {
+                info!(job = job_name, "Job lock acquired");
+                return Ok(Some(JobLock { conn, job_name: job_name.to_string() }));
+            }
+
+            if Instant::now() + POLL_INTERVAL > deadline {
+                warn!(job = job_name, "Job is running in another instance, giving up");
+                conn.close().await?;
+                return Ok(None);
+            }
+
+            if !logged {
+                info!(job = job_name, wait_s = wait.as_secs(), "Job is running in another instance, waiting");
+                logged = true;
+            }
+
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl JobLock {
+    pub async fn release(mut self) -> Result<()> {
+        sqlx::query_scalar!(
+            "SELECT pg_advisory_unlock(hashtextextended($1, 0))",
+            self.job_name
+        )
+            .fetch_one(&mut self.conn)
+            .await?;
+
+        self.conn.close().await?;
+        info!(job = self.job_name, "Job lock released");
+
+        Ok(())
+    }
+}
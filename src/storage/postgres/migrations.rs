@@ -0,0 +1,141 @@
+//! The migrations in `migrations/`, embedded at build time and tracked
+//! in `_sqlx_migrations` (the table the sqlx CLI uses too).
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use tracing::{info, warn};
+
+use super::PgStorage;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug)]
+pub enum MigrationState {
+    Applied(DateTime<Utc>),
+    Pending,
+    /// Applied, but the file changed since.
+    Modified,
+    /// A previous run of it failed half-way.
+    Failed,
+    /// Applied by a newer binary.
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+impl MigrationStatus {
+    /// The binary needs this migration (re)applied first.
+    fn is_behind(&self) -> bool {
+        matches!(
+            self.state,
+            MigrationState::Pending | MigrationState::Modified | MigrationState::Failed
+        )
+    }
+}
+
+impl PgStorage {
+    /// Every embedded migration and every applied one, by version.
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let table_exists = sqlx::query_scalar!(
+            r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "exists!""#
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        // Not checked at compile time: the table only exists once
+        // migrations ran
+        let mut applied: HashMap<i64, (Vec<u8>, DateTime<Utc>, bool)> = if table_exists {
+            sqlx::query_as::<_, (i64, Vec<u8>, DateTime<Utc>, bool)>(
+                "SELECT version, checksum, installed_on, success FROM _sqlx_migrations",
+            )
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|(version, checksum, at, success)| (version, (checksum, at, success)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut statuses = Vec::new();
+
+        for m in MIGRATOR.iter().filter(|m| !m.migration_type.is_down_migration()) {
+            let state = match applied.remove(&m.version) {
+                None => MigrationState::Pending,
+                Some((_, _, false)) => MigrationState::Failed,
+                Some((checksum, _, _)) if checksum != *m.checksum => MigrationState::Modified,
+                Some((_, at, _)) => MigrationState::Applied(at),
+            };
+
+            statuses.push(MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                state,
+            });
+        }
+
+        for version in applied.into_keys() {
+            statuses.push(MigrationStatus {
+                version,
+                description: String::new(),
+                state: MigrationState::Unknown,
+            });
+        }
+
+        statuses.sort_by_key(|s| s.version);
+        Ok(statuses)
+    }
+
+    /// Apply the pending migrations. Returns the versions applied.
+    pub async fn run_migrations(&self) -> Result<Vec<i64>> {
+        let pending: Vec<i64> = self
+            .migration_status()
+            .await?
+            .iter()
+            .filter(|s| matches!(s.state, MigrationState::Pending))
+            .map(|s| s.version)
+            .collect();
+
+        // Refuses, as it should, a database migrated by a newer binary
+        MIGRATOR.run(&self.pool).await?;
+
+        Ok(pending)
+    }
+
+    /// Fail unless the database has every migration of this binary;
+    /// with `auto_migrate`, apply the pending ones instead.
+    pub async fn ensure_schema(&self, auto_migrate: bool) -> Result<()> {
+        let statuses = self.migration_status().await?;
+        let behind: Vec<&MigrationStatus> = statuses.iter().filter(|s| s.is_behind()).collect();
+
+        if statuses.iter().any(|s| matches!(s.state, MigrationState::Unknown)) {
+            warn!("Database has migrations this binary does not know, it may be outdated");
+        }
+
+        let Some(latest) = behind.last() else {
+            return Ok(());
+        };
+
+        if auto_migrate && behind.iter().all(|s| matches!(s.state, MigrationState::Pending)) {
+            let applied = self.run_migrations().await?;
+            info!(count = applied.len(), "Applied pending migrations");
+            return Ok(());
+        }
+
+        bail!(
+            "database schema is behind this binary: {} migration(s) pending, modified or failed, \
+             up to {} {}; see `migrate status`, apply with `migrate run` (or --auto-migrate)",
+            behind.len(),
+            latest.version,
+            latest.description
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Detail fetch queue (`detail_queue`).
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::PgStorage;
+
+/// A claimed queue item; `attempts` includes the current one.
+#[derive(Debug)]
+pub struct QueuedItem {
+    pub id: i64,
+    pub external_id: String,
+    pub url: String,
+    /// Listing base URL the link was found under.
+    pub category: Option<String>,
+    pub attempts: i32,
+}
+
+/// Items claimed together. Updates to them only apply while the claim
+/// still holds `token`, i.e. until it expired and another worker took
+/// the items.
+#[derive(Debug)]
+pub struct Claim {
+    pub token: String,
+    pub items: Vec<QueuedItem>,
+    /// Items given up on before claiming, after `max_attempts` claims
+    /// that never finished.
+    pub abandoned: u64,
+}
+
+impl PgStorage {
+    /// Enqueue item links. Links already queued go back to pending
+    /// unless they are waiting there already, so a listing seen again is
+    /// fetched again. Returns the number of rows inserted or reset.
+    pub async fn enqueue_details(
+        &self,
+        external_ids: &[String],
+        urls: &[String],
+        category: &str,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO houses_data.detail_queue (external_id, url, category)
+            SELECT ids.external_id, ids.url, $3
+            FROM UNNEST($1::text[], $2::text[]) AS ids (external_id, url)
+            ON CONFLICT (external_id) DO UPDATE SET
+                url = EXCLUDED.url,
+                category = EXCLUDED.category,
+                status = 'pending',
+                attempts = 0,
+                last_error = NULL,
+                available_at = now(),
+                enqueued_at = now(),
+                updated_at = now()
+            WHERE detail_queue.status <> 'pending'
+            "#,
+            external_ids,
+            urls,
+            category
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Claim up to `limit` items for `visibility` under a fresh token.
+    /// An item whose worker dies is claimable again once the visibility
+    /// timeout expires, until it was claimed `max_attempts` times: then
+    /// it is marked `failed` instead of being handed out again.
+    pub async fn claim_details(
+        &self,
+        limit: i64,
+        visibility: chrono::Duration,
+        max_attempts: i32,
+    ) -> Result<Claim> {
+        let abandoned = sqlx::query!(
+            r#"
+            UPDATE houses_data.detail_queue
+            SET status = 'failed',
+                last_error = 'gave up after ' || attempts || ' claims' || COALESCE(': ' || last_error, ''),
+                locked_until = NULL,
+                claim_token = NULL,
+                updated_at = now()
+            WHERE status = 'pending'
+              AND attempts >= $1
+              AND available_at <= now()
+              AND (locked_until IS NULL OR locked_until < now())
+            "#,
+            max_attempts
+        )
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        let token = Uuid::new_v4().to_string();
+        let locked_until = Utc::now() + visibility;
+
+        let items = sqlx::query_as!(
+            QueuedItem,
+            r#"
+            UPDATE houses_data.detail_queue q
+            SET attempts = q.attempts + 1,
+                locked_until = $2,
+                claim_token = $3,
+                updated_at = now()
+            WHERE q.id IN (
+                SELECT id
+                FROM houses_data.detail_queue
+                WHERE status = 'pending'
+                  AND available_at <= now()
+                  AND (locked_until IS NULL OR locked_until < now())
+                ORDER BY available_at, id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING q.id, q.external_id, q.url, q.category, q.attempts
+            "#,
+            limit,
+            locked_until,
+            token
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(Claim { token, items, abandoned })
+    }
+
+    /// Push the visibility timeout of the items still held under
+    /// `token` to `visibility` from now.
+    pub async fn extend_claim(&self, token: &str, visibility: chrono::Duration) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE houses_data.detail_queue
+            SET locked_until = $2,
+                updated_at = now()
+            WHERE claim_token = $1
+              AND status = 'pending'
+            "#,
+            token,
+            Utc::now() + visibility
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Mark items done. Returns how many were still held under `token`.
+    pub async fn complete_details(&self, ids: &[i64], token: &str) -> Result<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE houses_data.detail_queue
+            SET status = 'done',
+                last_error = NULL,
+                locked_until = NULL,
+                claim_token = NULL,
+                updated_at = now()
+            WHERE id = ANY($1)
+              AND claim_token = $2
+            "#,
+            ids,
+            token
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Record a failed attempt: retried from `retry_at`, or given up on
+    /// (`failed`) when it is `None`. Returns whether the item was still
+    /// held under `token`.
+    pub async fn fail_detail(
+        &self,
+        id: i64,
+        token: &str,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE houses_data.detail_queue
+            SET status = CASE WHEN $4::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                last_error = $3,
+                available_at = COALESCE($4, available_at),
+                locked_until = NULL,
+                claim_token = NULL,
+                updated_at = now()
+            WHERE id = $1
+              AND claim_token = $2
+            "#,
+            id,
+            token,
+            error,
+            retry_at
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Give back claimed items untouched (e.g. on shutdown), without
+    /// counting the attempt.
+    pub async fn release_details(&self, ids: &[i64], token: &str) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.detail_queue
+            SET attempts = attempts - 1,
+                locked_until = NULL,
+                claim_token = NULL,
+                updated_at = now()
+            WHERE id = ANY($1)
+              AND claim_token = $2
+            "#,
+            ids,
+            token
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
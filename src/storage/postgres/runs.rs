@@ -0,0 +1,125 @@
+//! Scrape run audit (`scrape_runs`).
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::PgStorage;
+use crate::storage::ScrapeRunStats;
+
+/// One `scrape_runs` row, for the `runs` listing.
+#[derive(Debug)]
+pub struct ScrapeRunRow {
+    pub id: i64,
+    pub started_at: DateTime<Utc>,
+    pub status: String,
+    pub resumed: bool,
+    pub duration_ms: Option<i64>,
+    pub pages_visited: i32,
+    pub links_found: i32,
+    pub items_new: i32,
+    pub items_updated: i32,
+    pub items_unchanged: i32,
+    pub items_failed: i32,
+    pub requests: i64,
+    pub bytes_downloaded: i64,
+    pub errors: Value,
+}
+
+impl PgStorage {
+    pub async fn record_scrape_run_start(
+        &self,
+        checkpoint_id: i64,
+        resumed: bool,
+        config: &Value,
+    ) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO houses_data.scrape_runs (checkpoint_id, resumed, config)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+            checkpoint_id,
+            resumed,
+            config
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    pub async fn record_scrape_run_end(
+        &self,
+        run_id: i64,
+        status: &str,
+        stats: &ScrapeRunStats,
+        duration_ms: i64,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE houses_data.scrape_runs
+            SET finished_at = now(),
+                status = $2,
+                pages_visited = $3,
+                links_found = $4,
+                items_new = $5,
+                items_updated = $6,
+                items_unchanged = $7,
+                items_failed = $8,
+                requests = $9,
+                bytes_downloaded = $10,
+                duration_ms = $11,
+                errors = $12
+            WHERE id = $1
+            "#,
+            run_id,
+            status,
+            stats.pages_visited as i32,
+            stats.links_found as i32,
+            stats.items_new as i32,
+            stats.items_updated as i32,
+            stats.items_unchanged as i32,
+            stats.items_failed as i32,
+            stats.requests as i64,
+            stats.bytes_downloaded as i64,
+            duration_ms,
+            serde_json::to_value(&stats.errors)?
+        )
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_scrape_runs(&self, limit: i64) -> Result<Vec<ScrapeRunRow>> {
+        let rows = sqlx::query_as!(
+            ScrapeRunRow,
+            r#"
+            SELECT
+                id,
+                started_at,
+                status,
+                resumed,
+                duration_ms,
+                pages_visited,
+                links_found,
+                items_new,
+                items_updated,
+                items_unchanged,
+                items_failed,
+                requests,
+                bytes_downloaded,
+                errors
+            FROM houses_data.scrape_runs
+            ORDER BY started_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,72 @@
+//! Counts behind the `stats` command.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use super::PgStorage;
+
+#[derive(Debug)]
+pub struct Overview {
+    pub active: i64,
+    pub suspected: i64,
+    pub deleted: i64,
+    pub images: i64,
+    pub images_downloaded: i64,
+    pub queue_pending: i64,
+    pub queue_failed: i64,
+}
+
+#[derive(Debug)]
+pub struct DailyStats {
+    pub day: NaiveDate,
+    pub active_count: i32,
+    pub created_count: i32,
+    pub reactivated_count: i32,
+    pub deleted_count: i32,
+}
+
+impl PgStorage {
+    pub async fn fetch_overview(&self) -> Result<Overview> {
+        let overview = sqlx::query_as!(
+            Overview,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM houses_data.list_am_houses
+                 WHERE deleted_at IS NULL) AS "active!",
+                (SELECT COUNT(*) FROM houses_data.list_am_houses
+                 WHERE deleted_at IS NULL AND removal_suspected_at IS NOT NULL) AS "suspected!",
+                (SELECT COUNT(*) FROM houses_data.list_am_houses
+                 WHERE deleted_at IS NOT NULL) AS "deleted!",
+                (SELECT COUNT(*) FROM houses_data.list_am_images) AS "images!",
+                (SELECT COUNT(*) FROM houses_data.list_am_images
+                 WHERE downloaded_at IS NOT NULL) AS "images_downloaded!",
+                (SELECT COUNT(*) FROM houses_data.detail_queue
+                 WHERE status = 'pending') AS "queue_pending!",
+                (SELECT COUNT(*) FROM houses_data.detail_queue
+                 WHERE status = 'failed') AS "queue_failed!"
+            "#
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(overview)
+    }
+
+    /// The last `days` rows of `list_am_daily_stats`, newest first.
+    pub async fn fetch_daily_stats(&self, days: i64) -> Result<Vec<DailyStats>> {
+        let rows = sqlx::query_as!(
+            DailyStats,
+            r#"
+            SELECT day, active_count, created_count, reactivated_count, deleted_count
+            FROM houses_data.list_am_daily_stats
+            ORDER BY day DESC
+            LIMIT $1
+            "#,
+            days
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,105 @@
+//! Records exchanged with every storage backend.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+/// Outcome of a batch save, by listing.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SaveStats {
+    pub new: usize,
+    /// Tracked fields changed, or a removed listing reappeared.
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl SaveStats {
+    pub fn saved(&self) -> usize {
+        self.new + self.updated + self.unchanged
+    }
+}
+
+/// One removal-checker probe, as stored in `list_am_checks`.
+#[derive(Debug)]
+pub struct CheckRecord {
+    pub house_id: i64,
+    pub method: &'static str,
+    pub http_status: Option<i16>,
+    pub final_url: Option<String>,
+    pub latency_ms: i32,
+    pub verdict: &'static str,
+    pub reason: Option<String>,
+    pub error_kind: Option<&'static str>,
+    pub action: &'static str,
+}
+
+/// An active listing as seen by the removal checker.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActiveHouse {
+    pub id: i64,
+    pub external_id: String,
+    pub url: String,
+    /// Last renewal, or creation when never renewed.
+    pub listed_at: Option<DateTime<Utc>>,
+    pub next_check_at: DateTime<Utc>,
+    pub removal_suspected_at: Option<DateTime<Utc>>,
+}
+
+/// Checker run state: when the run started (listings due before that
+/// are processed) and the `(next_check_at, id)` keyset cursor.
+#[derive(Debug, sqlx::FromRow)]
+pub struct CheckerRun {
+    pub started_at: DateTime<Utc>,
+    pub cursor_at: Option<DateTime<Utc>>,
+    pub cursor_id: i64,
+}
+
+/// Where a scrape run is: the page in progress and the items of that
+/// page already saved.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ScrapeCheckpoint {
+    pub id: i64,
+    pub start_page: i32,
+    pub end_page: i32,
+    pub page: i32,
+    #[sqlx(json)]
+    pub saved_external_ids: Vec<String>,
+}
+
+/// Counters of one scrape run.
+#[derive(Debug, Default)]
+pub struct ScrapeRunStats {
+    pub pages_visited: usize,
+    pub links_found: usize,
+    pub items_new: usize,
+    pub items_updated: usize,
+    pub items_unchanged: usize,
+    pub items_failed: usize,
+    /// Listings written to the file sinks.
+    pub items_written: usize,
+    pub requests: u64,
+    pub bytes_downloaded: u64,
+    /// `<stage>:<kind>` -> count, e.g. `item:timeout`.
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl ScrapeRunStats {
+    pub fn add_error(&mut self, stage: &str, kind: &str) {
+        *self.errors.entry(format!("{}:{}", stage, kind)).or_default() += 1;
+    }
+
+    /// The counters kept in `job_runs`.
+    pub fn counters(&self) -> Value {
+        json!({
+            "new": self.items_new,
+            "updated": self.items_updated,
+            "unchanged": self.items_unchanged,
+            "failed": self.items_failed,
+        })
+    }
+
+    pub fn items_saved(&self) -> usize {
+        self.items_new + self.items_updated + self.items_unchanged
+    }
+}
@@ -0,0 +1,1143 @@
+//! SQLite backend, for running the scrape and removal-check pipelines
+//! without a Postgres server: `sqlite:houses.db` for a local file,
+//! `sqlite::memory:` for a throwaway database.
+//!
+//! The schema (`migrations_sqlite`) mirrors the Postgres tables these
+//! pipelines write and is applied on open. As there, phones, images
+//! and features that disappear from a listing move to
+//! `list_am_child_history`.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::types::Json;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tokio::time::Instant;
+use tracing::info;
+
+use super::{
+    first_check_at, parse_iso, ActiveHouse, CheckRecord, CheckerRun, SaveStats, ScrapeCheckpoint,
+    ScrapeRunStats, Storage,
+};
+use crate::crawler::models::HouseDetails;
+use crate::metrics;
+use crate::storage::versions;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+/// Columns selected into an `ActiveHouse`.
+const ACTIVE_HOUSE_COLUMNS: &str = r#"
+    id,
+    external_id,
+    url,
+    COALESCE(updated_at, created_at) AS listed_at,
+    next_check_at,
+    removal_suspected_at
+"#;
+
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+/// What `save_house_tx` did to one listing.
+enum Saved {
+    New,
+    Updated,
+    Unchanged,
+}
+
+impl SqliteStorage {
+    /// Open (creating it if needed) and migrate the database.
+    pub async fn open(database_url: &str) -> Result<Self> {
+        let in_memory = database_url.contains(":memory:") || database_url.contains("mode=memory");
+
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+
+        // Every connection to `:memory:` is a database of its own, so an
+        // in-memory database lives on a single, never recycled connection
+        let pool = if in_memory {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?
+        } else {
+            SqlitePoolOptions::new()
+                .max_connections(5)
+                .connect_with(options.journal_mode(SqliteJournalMode::Wal))
+                .await?
+        };
+
+        MIGRATOR.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    async fn save_house_tx(tx: &mut Transaction<'_, Sqlite>, house: &HouseDetails) -> Result<(i64, Saved)> {
+        let previous: Option<(i64, Option<String>)> = sqlx::query_as(
+            "SELECT id, deleted_at FROM list_am_houses WHERE external_id = ?",
+        )
+            .bind(&house.external_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        let now = Utc::now();
+        let house_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO list_am_houses (
+                external_id,
+                url,
+                title,
+                price,
+                seller_name,
+                condition,
+                rooms,
+                house_area_m2,
+                land_area_m2,
+                construction_type,
+                floors,
+                bathrooms,
+                garage,
+                renovation,
+                furniture,
+                description,
+                location,
+                amenities,
+                comfort,
+                ceiling_height,
+                prepayment,
+                utility_payments,
+                lease_type,
+                minimum_rental_period,
+                sewerage,
+                parking,
+                entrance,
+                location_from_street,
+                elevator,
+                floor_area,
+                created_at,
+                updated_at,
+                scraped_at,
+                category,
+                next_check_at
+            )
+            VALUES (
+                ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+            )
+            ON CONFLICT (external_id) DO UPDATE SET
+                title = excluded.title,
+                price = excluded.price,
+                seller_name = excluded.seller_name,
+                condition = excluded.condition,
+                rooms = excluded.rooms,
+                house_area_m2 = excluded.house_area_m2,
+                land_area_m2 = excluded.land_area_m2,
+                construction_type = excluded.construction_type,
+                floors = excluded.floors,
+                bathrooms = excluded.bathrooms,
+                garage = excluded.garage,
+                renovation = excluded.renovation,
+                furniture = excluded.furniture,
+                description = excluded.description,
+                location = excluded.location,
+                amenities = excluded.amenities,
+                comfort = excluded.comfort,
+                ceiling_height = excluded.ceiling_height,
+                prepayment = excluded.prepayment,
+                utility_payments = excluded.utility_payments,
+                lease_type = excluded.lease_type,
+                minimum_rental_period = excluded.minimum_rental_period,
+                sewerage = excluded.sewerage,
+                parking = excluded.parking,
+                entrance = excluded.entrance,
+                location_from_street = excluded.location_from_street,
+                elevator = excluded.elevator,
+                floor_area = excluded.floor_area,
+                updated_at = excluded.updated_at,
+                category = COALESCE(excluded.category, category),
+                next_check_at = CASE
+                    WHEN deleted_at IS NOT NULL THEN excluded.next_check_at
+                    ELSE next_check_at
+                END,
+                relist_count = relist_count + (deleted_at IS NOT NULL),
+                deleted_at = NULL,
+                removal_suspected_at = NULL,
+                removal_reason = NULL,
+                scraped_at = excluded.scraped_at
+            RETURNING id
+            "#,
+        )
+            .bind(&house.external_id)
+            .bind(&house.url)
+            .bind(&house.title)
+            .bind(&house.price)
+            .bind(&house.contact.seller_name)
+            .bind(&house.condition)
+            .bind(house.rooms)
+            .bind(house.house_area_m2)
+            .bind(house.land_area_m2)
+            .bind(&house.construction_type)
+            .bind(house.floors)
+            .bind(house.bathrooms)
+            .bind(&house.garage)
+            .bind(&house.renovation)
+            .bind(&house.furniture)
+            .bind(&house.description)
+            .bind(&house.location)
+            .bind(&house.amenities)
+            .bind(&house.comfort)
+            .bind(&house.ceiling_height)
+            .bind(&house.prepayment)
+            .bind(&house.utility_payments)
+            .bind(&house.lease_type)
+            .bind(&house.minimum_rental_period)
+            .bind(&house.sewerage)
+            .bind(&house.parking)
+            .bind(&house.entrance)
+            .bind(&house.location_from_street)
+            .bind(&house.elevator)
+            .bind(&house.floor_area)
+            .bind(parse_iso(&house.created_at))
+            .bind(parse_iso(&house.updated_at))
+            .bind(now)
+            .bind(&house.category)
+            .bind(first_check_at(house, now))
+            .fetch_one(&mut **tx)
+            .await?;
+
+        // Version history, diffed against the latest snapshot
+        let snapshot = versions::tracked_snapshot(&tracked_row(house)?);
+
+        let latest: Option<(i32, Json<Value>)> = sqlx::query_as(
+            r#"
+            SELECT version, snapshot
+            FROM list_am_house_versions
+            WHERE house_id = ?
+            ORDER BY version DESC
+            LIMIT 1
+            "#,
+        )
+            .bind(house_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        let (version, changed, diff) = match &latest {
+            Some((version, Json(prev))) => {
+                let (changed, diff) = versions::diff_snapshots(prev, &snapshot);
+                (version + 1, changed, diff)
+            }
+            None => (1, vec![], serde_json::json!({})),
+        };
+
+        if latest.is_none() || !changed.is_empty() {
+            sqlx::query(
+                r#"
+                INSERT INTO list_am_house_versions (house_id, version, changed_fields, diff, snapshot)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+                .bind(house_id)
+                .bind(version)
+                .bind(Json(&changed))
+                .bind(Json(&diff))
+                .bind(Json(&snapshot))
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        // Lifecycle
+        let saved = match &previous {
+            None => {
+                Self::record_event(tx, house_id, "created").await?;
+                Saved::New
+            }
+            Some((_, Some(_))) => {
+                info!(external_id = %house.external_id, "Removed listing reappeared, reactivating");
+                Self::record_event(tx, house_id, "reactivated").await?;
+                Saved::Updated
+            }
+            Some(_) if !changed.is_empty() => Saved::Updated,
+            Some(_) => Saved::Unchanged,
+        };
+
+        let features = [
+            ("appliances", &house.appliances),
+            ("service_lines", &house.service_lines),
+            ("facilities", &house.facilities),
+        ];
+
+        // Children no longer on the listing go to the child history
+        Self::retire_children(
+            tx,
+            house_id,
+            "list_am_phones",
+            "phone",
+            "json_object('raw', raw, 'display', display, 'source', source)",
+            |row| house.contact.phones.iter().any(|p| row["raw"] == p.raw.as_str() && row["source"] == p.source.as_str()),
+        )
+            .await?;
+
+        Self::retire_children(
+            tx,
+            house_id,
+            "list_am_images",
+            "image",
+            "json_object('position', position, 'url', url)",
+            |row| house.images.iter().any(|url| row["url"] == url.as_str()),
+        )
+            .await?;
+
+        Self::retire_children(
+            tx,
+            house_id,
+            "list_am_features",
+            "feature",
+            "json_object('feature_type', feature_type, 'value', value)",
+            |row| {
+                features.iter().any(|(feature_type, values)| {
+                    row["feature_type"] == *feature_type && values.iter().any(|v| row["value"] == v.as_str())
+                })
+            },
+        )
+            .await?;
+
+        for phone in &house.contact.phones {
+            sqlx::query(
+                r#"
+                INSERT INTO list_am_phones (house_id, raw, display, source)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (house_id, source) DO UPDATE SET
+                    raw = excluded.raw,
+                    display = excluded.display
+                "#,
+            )
+                .bind(house_id)
+                .bind(&phone.raw)
+                .bind(&phone.display)
+                .bind(&phone.source)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        for (pos, url) in house.images.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO list_am_images (house_id, position, url)
+                VALUES (?, ?, ?)
+                ON CONFLICT (house_id, url) DO UPDATE SET position = excluded.position
+                "#,
+            )
+                .bind(house_id)
+                .bind(pos as i32)
+                .bind(url)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        for (feature_type, values) in features {
+            for value in values {
+                sqlx::query(
+                    r#"
+                    INSERT INTO list_am_features (house_id, feature_type, value)
+                    VALUES (?, ?, ?)
+                    ON CONFLICT (house_id, feature_type, value) DO NOTHING
+                    "#,
+                )
+                    .bind(house_id)
+                    .bind(feature_type)
+                    .bind(value)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+
+        // Price history only grows; unparseable dates are stored as NULL
+        for p in &house.price_history {
+            let date = parse_iso(&Some(p.date.clone()));
+
+            sqlx::query(
+                r#"
+                INSERT INTO list_am_price_history (house_id, date, price, diff)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (house_id, date, price, diff) DO NOTHING
+                "#,
+            )
+                .bind(house_id)
+                .bind(date)
+                .bind(&p.price)
+                .bind(&p.diff)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok((house_id, saved))
+    }
+
+    /// Move the rows of `table` (a child table of `kind`) that `keep`
+    /// rejects to `list_am_child_history`. `data` selects a row as JSON,
+    /// without `id` and `house_id`.
+    async fn retire_children(
+        tx: &mut Transaction<'_, Sqlite>,
+        house_id: i64,
+        table: &str,
+        kind: &str,
+        data: &str,
+        keep: impl Fn(&Value) -> bool,
+    ) -> Result<()> {
+        let rows: Vec<(i64, Json<Value>)> =
+            sqlx::query_as(&format!("SELECT id, {} FROM {} WHERE house_id = ?", data, table))
+                .bind(house_id)
+                .fetch_all(&mut **tx)
+                .await?;
+
+        for (id, Json(row)) in rows {
+            if keep(&row) {
+                continue;
+            }
+
+            sqlx::query(&format!("DELETE FROM {} WHERE id = ?", table))
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+
+            sqlx::query("INSERT INTO list_am_child_history (house_id, kind, data, removed_at) VALUES (?, ?, ?, ?)")
+                .bind(house_id)
+                .bind(kind)
+                .bind(Json(&row))
+                .bind(Utc::now())
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn record_event(tx: &mut Transaction<'_, Sqlite>, house_id: i64, event_type: &str) -> Result<()> {
+        sqlx::query("INSERT INTO list_am_listing_events (house_id, event_type, occurred_at) VALUES (?, ?, ?)")
+            .bind(house_id)
+            .bind(event_type)
+            .bind(Utc::now())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The tracked columns of a listing as a JSON row, named as in
+/// `list_am_houses`.
+fn tracked_row(house: &HouseDetails) -> Result<Value> {
+    let mut row = serde_json::to_value(house)?;
+    row["seller_name"] = serde_json::to_value(&house.contact.seller_name)?;
+    Ok(row)
+}
+
+/// `?, ?, ...` for `n` bound values.
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn save_houses_bulk(&self, houses: &[HouseDetails]) -> Result<SaveStats> {
+        let mut stats = SaveStats::default();
+
+        if houses.is_empty() {
+            return Ok(stats);
+        }
+
+        // Same contract as Postgres: the last occurrence of a listing wins
+        let mut by_external_id: HashMap<&str, &HouseDetails> = HashMap::new();
+        for house in houses {
+            by_external_id.insert(house.external_id.as_str(), house);
+        }
+
+        let started = Instant::now();
+        let mut tx = self.pool.begin().await?;
+
+        for house in by_external_id.into_values() {
+            match Self::save_house_tx(&mut tx, house).await?.1 {
+                Saved::New => stats.new += 1,
+                Saved::Updated => stats.updated += 1,
+                Saved::Unchanged => stats.unchanged += 1,
+            }
+        }
+
+        tx.commit().await?;
+
+        metrics::record_upsert("sqlite", started.elapsed());
+        metrics::record_saved("new", stats.new);
+        metrics::record_saved("updated", stats.updated);
+        metrics::record_saved("unchanged", stats.unchanged);
+
+        Ok(stats)
+    }
+
+    async fn save_house(&self, house: &HouseDetails) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        let (id, _) = Self::save_house_tx(&mut tx, house).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    async fn mark_houses_as_deleted(&self, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let marked: Vec<i64> = {
+            let sql = format!(
+                r#"
+                UPDATE list_am_houses
+                SET deleted_at = ?,
+                    removal_suspected_at = NULL
+                WHERE id IN ({})
+                  AND deleted_at IS NULL
+                RETURNING id
+                "#,
+                placeholders(ids.len())
+            );
+
+            let mut query = sqlx::query_scalar(&sql).bind(now);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(&mut *tx).await?
+        };
+
+        for id in &marked {
+            sqlx::query("INSERT INTO list_am_listing_events (house_id, event_type, occurred_at) VALUES (?, 'deleted', ?)")
+                .bind(id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        metrics::record_deleted(marked.len());
+
+        Ok(())
+    }
+
+    async fn fetch_due_houses_batch(
+        &self,
+        limit: i64,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<Vec<ActiveHouse>> {
+        let sql = format!(
+            r#"
+            SELECT {}
+            FROM list_am_houses
+            WHERE deleted_at IS NULL
+              AND next_check_at <= ?
+              AND (next_check_at, id) > (COALESCE(?, ''), ?)
+            ORDER BY next_check_at, id
+            LIMIT ?
+            "#,
+            ACTIVE_HOUSE_COLUMNS
+        );
+
+        let rows = sqlx::query_as(&sql)
+            .bind(due_before)
+            .bind(cursor_at)
+            .bind(cursor_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows)
+    }
+
+    async fn count_due_houses(
+        &self,
+        due_before: DateTime<Utc>,
+        cursor_at: Option<DateTime<Utc>>,
+        cursor_id: i64,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM list_am_houses
+            WHERE deleted_at IS NULL
+              AND next_check_at <= ?
+              AND (next_check_at, id) > (COALESCE(?, ''), ?)
+            "#,
+        )
+            .bind(due_before)
+            .bind(cursor_at)
+            .bind(cursor_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn fetch_house_for_check(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<(ActiveHouse, Option<DateTime<Utc>>)>> {
+        let sql = format!("SELECT {} FROM list_am_houses WHERE external_id = ?", ACTIVE_HOUSE_COLUMNS);
+
+        let Some(house) = sqlx::query_as::<_, ActiveHouse>(&sql)
+            .bind(external_id)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let deleted_at = sqlx::query_scalar("SELECT deleted_at FROM list_am_houses WHERE id = ?")
+            .bind(house.id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(Some((house, deleted_at)))
+    }
+
+    async fn schedule_next_checks(&self, ids: &[i64], next_check_at: &[DateTime<Utc>]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        for (id, at) in ids.iter().zip(next_check_at) {
+            sqlx::query("UPDATE list_am_houses SET next_check_at = ?, last_checked_at = ? WHERE id = ?")
+                .bind(at)
+                .bind(now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn flag_removal_suspected(&self, ids: &[i64], reasons: &[String]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        for (id, reason) in ids.iter().zip(reasons) {
+            sqlx::query(
+                r#"
+                UPDATE list_am_houses
+                SET removal_suspected_at = COALESCE(removal_suspected_at, ?),
+                    removal_reason = ?
+                WHERE id = ?
+                "#,
+            )
+                .bind(now)
+                .bind(reason)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear_removal_suspected(&self, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!(
+            "UPDATE list_am_houses SET removal_suspected_at = NULL, removal_reason = NULL WHERE id IN ({})",
+            placeholders(ids.len())
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        query.execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn record_checks(&self, checks: &[CheckRecord]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        for c in checks {
+            sqlx::query(
+                r#"
+                INSERT INTO list_am_checks (
+                    house_id,
+                    checked_at,
+                    method,
+                    http_status,
+                    final_url,
+                    latency_ms,
+                    verdict,
+                    reason,
+                    error_kind,
+                    action
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+                .bind(c.house_id)
+                .bind(now)
+                .bind(c.method)
+                .bind(c.http_status)
+                .bind(&c.final_url)
+                .bind(c.latency_ms)
+                .bind(c.verdict)
+                .bind(&c.reason)
+                .bind(c.error_kind)
+                .bind(c.action)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn begin_checker_run(&self, job_name: &str) -> Result<CheckerRun> {
+        let run = sqlx::query_as(
+            r#"
+            INSERT INTO checker_state (job_name, run_started_at, updated_at)
+            VALUES (?1, ?2, ?2)
+            ON CONFLICT (job_name) DO UPDATE SET
+                last_id = CASE WHEN run_finished_at IS NULL THEN last_id ELSE 0 END,
+                last_next_check_at = CASE WHEN run_finished_at IS NULL THEN last_next_check_at ELSE NULL END,
+                run_started_at = CASE WHEN run_finished_at IS NULL THEN run_started_at ELSE ?2 END,
+                run_finished_at = NULL,
+                updated_at = ?2
+            RETURNING
+                run_started_at AS started_at,
+                last_next_check_at AS cursor_at,
+                last_id AS cursor_id
+            "#,
+        )
+            .bind(job_name)
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(run)
+    }
+
+    async fn save_checker_cursor(&self, job_name: &str, cursor_at: DateTime<Utc>, cursor_id: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE checker_state SET last_next_check_at = ?, last_id = ?, updated_at = ? WHERE job_name = ?",
+        )
+            .bind(cursor_at)
+            .bind(cursor_id)
+            .bind(Utc::now())
+            .bind(job_name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn finish_checker_run(&self, job_name: &str) -> Result<()> {
+        sqlx::query("UPDATE checker_state SET run_finished_at = ?1, updated_at = ?1 WHERE job_name = ?2")
+            .bind(Utc::now())
+            .bind(job_name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn begin_scrape_run(&self, category: &str, start_page: i32, end_page: i32) -> Result<ScrapeCheckpoint> {
+        let checkpoint = sqlx::query_as(
+            r#"
+            INSERT INTO scrape_checkpoints (category, start_page, end_page, page, started_at, updated_at)
+            VALUES (?1, ?2, ?3, ?2, ?4, ?4)
+            RETURNING id, start_page, end_page, page, saved_external_ids
+            "#,
+        )
+            .bind(category)
+            .bind(start_page)
+            .bind(end_page)
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(checkpoint)
+    }
+
+    async fn find_unfinished_scrape_run(&self, category: &str) -> Result<Option<ScrapeCheckpoint>> {
+        let checkpoint = sqlx::query_as(
+            r#"
+            SELECT id, start_page, end_page, page, saved_external_ids
+            FROM scrape_checkpoints
+            WHERE category = ?
+              AND finished_at IS NULL
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+        )
+            .bind(category)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(checkpoint)
+    }
+
+    async fn save_scrape_checkpoint(&self, run_id: i64, page: i32, saved_external_ids: &[String]) -> Result<()> {
+        sqlx::query("UPDATE scrape_checkpoints SET page = ?, saved_external_ids = ?, updated_at = ? WHERE id = ?")
+            .bind(page)
+            .bind(Json(saved_external_ids))
+            .bind(Utc::now())
+            .bind(run_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn finish_scrape_run(&self, run_id: i64) -> Result<()> {
+        sqlx::query("UPDATE scrape_checkpoints SET finished_at = ?1, updated_at = ?1 WHERE id = ?2")
+            .bind(Utc::now())
+            .bind(run_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_scrape_run_start(&self, checkpoint_id: i64, resumed: bool, config: &Value) -> Result<i64> {
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO scrape_runs (checkpoint_id, resumed, config, started_at)
+            VALUES (?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+            .bind(checkpoint_id)
+            .bind(resumed)
+            .bind(Json(config))
+            .bind(Utc::now())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    async fn record_scrape_run_end(
+        &self,
+        run_id: i64,
+        status: &str,
+        stats: &ScrapeRunStats,
+        duration_ms: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE scrape_runs
+            SET finished_at = ?,
+                status = ?,
+                pages_visited = ?,
+                links_found = ?,
+                items_new = ?,
+                items_updated = ?,
+                items_unchanged = ?,
+                items_failed = ?,
+                requests = ?,
+                bytes_downloaded = ?,
+                duration_ms = ?,
+                errors = ?
+            WHERE id = ?
+            "#,
+        )
+            .bind(Utc::now())
+            .bind(status)
+            .bind(stats.pages_visited as i32)
+            .bind(stats.links_found as i32)
+            .bind(stats.items_new as i32)
+            .bind(stats.items_updated as i32)
+            .bind(stats.items_unchanged as i32)
+            .bind(stats.items_failed as i32)
+            .bind(stats.requests as i64)
+            .bind(stats.bytes_downloaded as i64)
+            .bind(duration_ms)
+            .bind(Json(&stats.errors))
+            .bind(run_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::crawler::models::{ContactPhone, PriceHistory};
+
+    async fn storage() -> SqliteStorage {
+        SqliteStorage::open("sqlite::memory:").await.unwrap()
+    }
+
+    fn house(external_id: &str, title: &str) -> HouseDetails {
+        HouseDetails {
+            external_id: external_id.to_string(),
+            url: format!("https://www.list.am/en/item/{}", external_id),
+            title: Some(title.to_string()),
+            ..Default::default()
+        }
+    }
+
+    async fn id_of(storage: &SqliteStorage, external_id: &str) -> i64 {
+        let (house, _) = storage.fetch_house_for_check(external_id).await.unwrap().unwrap();
+        house.id
+    }
+
+    async fn events(storage: &SqliteStorage, house_id: i64) -> Vec<String> {
+        sqlx::query_scalar("SELECT event_type FROM list_am_listing_events WHERE house_id = ? ORDER BY id")
+            .bind(house_id)
+            .fetch_all(&storage.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn bulk_save_counts_new_updated_and_unchanged() {
+        let storage = storage().await;
+
+        let stats = storage.save_houses_bulk(&[house("1", "A"), house("2", "B")]).await.unwrap();
+        assert_eq!((stats.new, stats.updated, stats.unchanged), (2, 0, 0));
+
+        let stats = storage.save_houses_bulk(&[house("1", "A"), house("2", "B, edited")]).await.unwrap();
+        assert_eq!((stats.new, stats.updated, stats.unchanged), (0, 1, 1));
+
+        let versions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM list_am_house_versions")
+            .fetch_one(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(versions, 3);
+    }
+
+    #[tokio::test]
+    async fn bulk_save_keeps_the_last_duplicate() {
+        let storage = storage().await;
+
+        let stats = storage.save_houses_bulk(&[house("1", "first"), house("1", "last")]).await.unwrap();
+        assert_eq!(stats.saved(), 1);
+
+        let title: String = sqlx::query_scalar("SELECT title FROM list_am_houses WHERE external_id = '1'")
+            .fetch_one(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(title, "last");
+    }
+
+    #[tokio::test]
+    async fn mark_as_deleted_is_recorded_once() {
+        let storage = storage().await;
+        storage.save_houses_bulk(&[house("1", "A"), house("2", "B")]).await.unwrap();
+        let id = id_of(&storage, "1").await;
+
+        storage.mark_houses_as_deleted(&[id]).await.unwrap();
+        storage.mark_houses_as_deleted(&[id]).await.unwrap();
+
+        let (_, deleted_at) = storage.fetch_house_for_check("1").await.unwrap().unwrap();
+        assert!(deleted_at.is_some());
+        assert_eq!(events(&storage, id).await, ["created", "deleted"]);
+
+        // Deleted listings are not due for a check
+        let due = storage.fetch_due_houses_batch(10, Utc::now() + Duration::days(365), None, 0).await.unwrap();
+        assert_eq!(due.iter().map(|h| h.external_id.as_str()).collect::<Vec<_>>(), ["2"]);
+    }
+
+    #[tokio::test]
+    async fn deleted_listing_is_reactivated_when_saved_again() {
+        let storage = storage().await;
+        storage.save_houses_bulk(&[house("1", "A")]).await.unwrap();
+        let id = id_of(&storage, "1").await;
+        storage.mark_houses_as_deleted(&[id]).await.unwrap();
+
+        let stats = storage.save_houses_bulk(&[house("1", "A")]).await.unwrap();
+        assert_eq!((stats.new, stats.updated, stats.unchanged), (0, 1, 0));
+
+        let (_, deleted_at) = storage.fetch_house_for_check("1").await.unwrap().unwrap();
+        assert!(deleted_at.is_none());
+
+        let relist_count: i64 = sqlx::query_scalar("SELECT relist_count FROM list_am_houses WHERE id = ?")
+            .bind(id)
+            .fetch_one(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(relist_count, 1);
+        assert_eq!(events(&storage, id).await, ["created", "deleted", "reactivated"]);
+    }
+
+    #[tokio::test]
+    async fn removed_children_move_to_the_child_history() {
+        let storage = storage().await;
+
+        let mut first = house("1", "A");
+        first.images = vec!["img/1".to_string(), "img/2".to_string()];
+        first.appliances = vec!["fridge".to_string()];
+        first.contact.phones = vec![ContactPhone {
+            raw: "091000000".to_string(),
+            display: "(091) 00-00-00".to_string(),
+            source: "direct".to_string(),
+        }];
+        storage.save_houses_bulk(&[first]).await.unwrap();
+
+        let mut second = house("1", "A");
+        second.images = vec!["img/2".to_string()];
+        storage.save_houses_bulk(&[second]).await.unwrap();
+
+        let mut history: Vec<(String, String)> =
+            sqlx::query_as("SELECT kind, data FROM list_am_child_history ORDER BY kind")
+                .fetch_all(&storage.pool)
+                .await
+                .unwrap();
+        history.sort();
+
+        let kinds: Vec<&str> = history.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, ["feature", "image", "phone"]);
+
+        let image: Value = serde_json::from_str(&history[1].1).unwrap();
+        assert_eq!(image["url"], "img/1");
+
+        let images: Vec<String> = sqlx::query_scalar("SELECT url FROM list_am_images")
+            .fetch_all(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(images, ["img/2"]);
+    }
+
+    #[tokio::test]
+    async fn due_houses_are_paged_by_keyset_cursor() {
+        let storage = storage().await;
+        let houses: Vec<HouseDetails> = (1..=5).map(|i| house(&i.to_string(), "A")).collect();
+        storage.save_houses_bulk(&houses).await.unwrap();
+
+        // Due in reverse id order, so the cursor has to follow next_check_at
+        let base = Utc::now() - Duration::hours(1);
+        let mut ids = Vec::new();
+        let mut at = Vec::new();
+        for i in 1..=5 {
+            ids.push(id_of(&storage, &i.to_string()).await);
+            at.push(base - Duration::minutes(i));
+        }
+        storage.schedule_next_checks(&ids, &at).await.unwrap();
+
+        let due_before = Utc::now();
+        assert_eq!(storage.count_due_houses(due_before, None, 0).await.unwrap(), 5);
+
+        let mut seen = Vec::new();
+        let (mut cursor_at, mut cursor_id) = (None, 0);
+        loop {
+            let batch = storage.fetch_due_houses_batch(2, due_before, cursor_at, cursor_id).await.unwrap();
+            let Some(last) = batch.last() else { break };
+            cursor_at = Some(last.next_check_at);
+            cursor_id = last.id;
+            seen.extend(batch.iter().map(|h| h.external_id.clone()));
+
+            let remaining = storage.count_due_houses(due_before, cursor_at, cursor_id).await.unwrap();
+            assert_eq!(remaining, 5 - seen.len() as i64);
+        }
+
+        assert_eq!(seen, ["5", "4", "3", "2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn new_listings_are_not_due_at_once() {
+        let storage = storage().await;
+        storage.save_houses_bulk(&[house("1", "A")]).await.unwrap();
+
+        // No listing date: the stale interval, 6h give or take the jitter
+        let now = Utc::now();
+        assert_eq!(storage.count_due_houses(now + Duration::minutes(320), None, 0).await.unwrap(), 0);
+        assert_eq!(storage.count_due_houses(now + Duration::minutes(400), None, 0).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn unfinished_checker_run_resumes_from_its_cursor() {
+        let storage = storage().await;
+
+        let run = storage.begin_checker_run("check").await.unwrap();
+        assert_eq!((run.cursor_at, run.cursor_id), (None, 0));
+
+        let cursor_at = Utc::now();
+        storage.save_checker_cursor("check", cursor_at, 42).await.unwrap();
+
+        let resumed = storage.begin_checker_run("check").await.unwrap();
+        assert_eq!(resumed.started_at, run.started_at);
+        assert_eq!(resumed.cursor_id, 42);
+        assert_eq!(resumed.cursor_at.map(|t| t.timestamp_micros()), Some(cursor_at.timestamp_micros()));
+
+        storage.finish_checker_run("check").await.unwrap();
+
+        let fresh = storage.begin_checker_run("check").await.unwrap();
+        assert_eq!((fresh.cursor_at, fresh.cursor_id), (None, 0));
+    }
+
+    #[tokio::test]
+    async fn interrupted_scrape_run_keeps_the_items_saved_on_its_page() {
+        let storage = storage().await;
+
+        let run = storage.begin_scrape_run("rtam", 1, 5).await.unwrap();
+        assert!(run.saved_external_ids.is_empty());
+
+        let saved = vec!["300".to_string(), "100".to_string()];
+        storage.save_scrape_checkpoint(run.id, 3, &saved).await.unwrap();
+
+        let resumed = storage.find_unfinished_scrape_run("rtam").await.unwrap().unwrap();
+        assert_eq!((resumed.id, resumed.page), (run.id, 3));
+        assert_eq!(resumed.saved_external_ids, saved);
+
+        storage.save_scrape_checkpoint(run.id, 4, &[]).await.unwrap();
+        let resumed = storage.find_unfinished_scrape_run("rtam").await.unwrap().unwrap();
+        assert!(resumed.saved_external_ids.is_empty());
+
+        storage.finish_scrape_run(run.id).await.unwrap();
+        assert!(storage.find_unfinished_scrape_run("rtam").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn price_history_keeps_unparseable_dates_as_null() {
+        let storage = storage().await;
+
+        let mut h = house("1", "A");
+        h.price_history = vec![
+            PriceHistory {
+                date: "2026-10-01T12:00:00+04:00".to_string(),
+                price: "$100,000".to_string(),
+                diff: None,
+            },
+            PriceHistory {
+                date: "01.10.2026".to_string(),
+                price: "$95,000".to_string(),
+                diff: Some("-$5,000".to_string()),
+            },
+        ];
+        storage.save_house(&h).await.unwrap();
+
+        let rows: Vec<(Option<String>, String)> =
+            sqlx::query_as("SELECT date, price FROM list_am_price_history WHERE house_id = ? ORDER BY id")
+                .bind(id_of(&storage, "1").await)
+                .fetch_all(&storage.pool)
+                .await
+                .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].0.as_deref().is_some_and(|d| d.starts_with("2026-10-01T08:00:00")), "{:?}", rows);
+        assert_eq!(rows[1], (None, "$95,000".to_string()));
+    }
+}
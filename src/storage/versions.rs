@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+/// Columns of `list_am_houses` whose edits are recorded in
+/// `list_am_house_versions`. `updated_at` is left out on purpose:
+/// list.am bumps it on every renewal, which is not an edit.
+pub const TRACKED_FIELDS: &[&str] = &[
+    "title",
+    "price",
+    "seller_name",
+    "condition",
+    "rooms",
+    "house_area_m2",
+    "land_area_m2",
+    "construction_type",
+    "floors",
+    "bathrooms",
+    "garage",
+    "renovation",
+    "furniture",
+    "description",
+    "location",
+    "amenities",
+    "comfort",
+    "ceiling_height",
+    "prepayment",
+    "utility_payments",
+    "lease_type",
+    "minimum_rental_period",
+    "sewerage",
+    "parking",
+    "entrance",
+    "location_from_street",
+    "elevator",
+    "floor_area",
+];
+
+#[derive(Debug)]
+pub struct HouseVersion {
+    pub version: i32,
+    pub changed_fields: Vec<String>,
+    pub diff: Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Keep only the tracked fields of a `to_jsonb(list_am_houses)` row.
+pub fn tracked_snapshot(row: &Value) -> Value {
+    let mut out = Map::new();
+
+    for field in TRACKED_FIELDS {
+        let v = row.get(*field).cloned().unwrap_or(Value::Null);
+        out.insert(field.to_string(), v);
+    }
+
+    Value::Object(out)
+}
+
+/// Compare two tracked snapshots and return the changed field names
+/// together with a `{ field: { old, new } }` diff.
+pub fn diff_snapshots(old: &Value, new: &Value) -> (Vec<String>, Value) {
+    let mut changed = Vec::new();
+    let mut diff = Map::new();
+
+    for field in TRACKED_FIELDS {
+        let before = old.get(*field).unwrap_or(&Value::Null);
+        let after = new.get(*field).unwrap_or(&Value::Null);
+
+        if before != after {
+            changed.push(field.to_string());
+
+            let mut entry = Map::new();
+            entry.insert("old".to_string(), before.clone());
+            entry.insert("new".to_string(), after.clone());
+            diff.insert(field.to_string(), Value::Object(entry));
+        }
+    }
+
+    (changed, Value::Object(diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn row(price: Value, updated_at: &str) -> Value {
+        json!({
+            "id": 7,
+            "external_id": "123",
+            "title": "House in Arabkir",
+            "price": price,
+            "rooms": 4,
+            "updated_at": updated_at,
+            "scraped_at": "2026-10-19T10:00:00+00:00",
+        })
+    }
+
+    fn diff(old: &Value, new: &Value) -> (Vec<String>, Value) {
+        diff_snapshots(&tracked_snapshot(old), &tracked_snapshot(new))
+    }
+
+    #[test]
+    fn snapshot_keeps_only_tracked_fields() {
+        let snapshot = tracked_snapshot(&row(json!("$100,000"), "2026-10-01T00:00:00+00:00"));
+        let fields = snapshot.as_object().unwrap();
+
+        assert_eq!(fields.len(), TRACKED_FIELDS.len());
+        assert_eq!(fields["price"], "$100,000");
+        assert_eq!(fields["amenities"], Value::Null);
+        assert!(!fields.contains_key("updated_at"));
+        assert!(!fields.contains_key("external_id"));
+    }
+
+    #[test]
+    fn unchanged_row_has_no_diff() {
+        let r = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+        let (changed, diff) = diff(&r, &r.clone());
+
+        assert!(changed.is_empty());
+        assert_eq!(diff, json!({}));
+    }
+
+    #[test]
+    fn changed_tracked_field_is_diffed() {
+        let old = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+        let new = row(json!("$95,000"), "2026-10-01T00:00:00+00:00");
+        let (changed, diff) = diff(&old, &new);
+
+        assert_eq!(changed, ["price"]);
+        assert_eq!(diff, json!({ "price": { "old": "$100,000", "new": "$95,000" } }));
+    }
+
+    #[test]
+    fn changed_untracked_field_is_ignored() {
+        let old = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+        let new = row(json!("$100,000"), "2026-10-18T00:00:00+00:00");
+
+        assert!(diff(&old, &new).0.is_empty());
+    }
+
+    #[test]
+    fn null_to_value_and_back_is_diffed() {
+        let none = row(Value::Null, "2026-10-01T00:00:00+00:00");
+        let some = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+
+        let (changed, diff_set) = diff(&none, &some);
+        assert_eq!(changed, ["price"]);
+        assert_eq!(diff_set, json!({ "price": { "old": null, "new": "$100,000" } }));
+
+        let (changed, diff_cleared) = diff(&some, &none);
+        assert_eq!(changed, ["price"]);
+        assert_eq!(diff_cleared, json!({ "price": { "old": "$100,000", "new": null } }));
+    }
+
+    #[test]
+    fn missing_field_equals_null() {
+        let mut old = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+        old.as_object_mut().unwrap().insert("garage".to_string(), Value::Null);
+        let new = row(json!("$100,000"), "2026-10-01T00:00:00+00:00");
+
+        assert!(diff_snapshots(&old, &new).0.is_empty());
+    }
+}